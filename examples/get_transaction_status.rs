@@ -0,0 +1,32 @@
+#![allow(deprecated)]
+extern crate bitcoin_hashes;
+extern crate bitcoin_rpc_client;
+
+use bitcoin_hashes::hex::FromHex;
+use bitcoin_rpc_client::BitcoinCoreClient;
+use bitcoin_rpc_client::BitcoinRpcApi;
+use bitcoin_rpc_client::Error;
+use bitcoin_rpc_client::TransactionId;
+use std::env::var;
+
+fn main() -> Result<(), Error> {
+    let url = var("BITCOIN_CORE_URL").unwrap();
+    let user = var("BITCOIN_CORE_USER").unwrap();
+    let password = var("BITCOIN_CORE_PASSWORD").unwrap();
+    let txid = TransactionId::from_hex(&var("TXID").unwrap()).unwrap();
+
+    let client = BitcoinCoreClient::new(&url, &user, &password);
+
+    let transaction = client.get_raw_transaction_verbose(&txid)?;
+
+    // Transactions in the mempool have no block yet
+    match (transaction.blockhash, transaction.confirmations) {
+        (Some(blockhash), Some(confirmations)) => println!(
+            "Confirmed in block {} with {} confirmations",
+            blockhash, confirmations
+        ),
+        _ => println!("Waiting in the mempool"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,161 @@
+use base64;
+use jsonrpc_client::header::HeaderValue;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+/// Credentials used to authenticate against the node.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Auth {
+    UserPass {
+        username: String,
+        password: String,
+    },
+    /// The `.cookie` file written by bitcoind on startup if no `rpcpassword` is configured.
+    CookieFile(PathBuf),
+}
+
+impl Auth {
+    pub fn user_pass(username: &str, password: &str) -> Self {
+        Auth::UserPass {
+            username: username.to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    pub fn cookie_file<P: AsRef<Path>>(path: P) -> Self {
+        Auth::CookieFile(path.as_ref().to_path_buf())
+    }
+}
+
+/// Produces the `Authorization` header for every request.
+///
+/// bitcoind writes a new cookie on every restart, hence a cookie based
+/// authenticator can be refreshed once the node rejects the current one.
+pub(crate) struct Authenticator {
+    auth: Auth,
+    header: RwLock<HeaderValue>,
+}
+
+impl Authenticator {
+    pub(crate) fn new(auth: Auth) -> io::Result<Self> {
+        let header = match auth {
+            Auth::UserPass {
+                ref username,
+                ref password,
+            } => basic_auth_header(&format!("{}:{}", username, password)),
+            Auth::CookieFile(ref path) => read_cookie_file(path)?,
+        };
+
+        Ok(Authenticator {
+            auth,
+            header: RwLock::new(header),
+        })
+    }
+
+    pub(crate) fn header(&self) -> HeaderValue {
+        self.header.read().unwrap().clone()
+    }
+
+    /// Re-reads the cookie file and returns whether the credentials changed.
+    pub(crate) fn refresh(&self) -> bool {
+        let path = match self.auth {
+            Auth::CookieFile(ref path) => path,
+            Auth::UserPass { .. } => return false,
+        };
+
+        match read_cookie_file(path) {
+            Ok(new_header) => {
+                let mut header = self.header.write().unwrap();
+
+                if *header == new_header {
+                    return false;
+                }
+
+                info!("Re-read changed cookie file {}", path.display());
+                *header = new_header;
+                true
+            }
+            Err(e) => {
+                warn!("Unable to re-read cookie file {}: {}", path.display(), e);
+                false
+            }
+        }
+    }
+}
+
+fn read_cookie_file(path: &Path) -> io::Result<HeaderValue> {
+    let cookie = fs::read_to_string(path)?;
+    let cookie = cookie.trim();
+
+    if !cookie.contains(':') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not a valid cookie file", path.display()),
+        ));
+    }
+
+    Ok(basic_auth_header(cookie))
+}
+
+fn basic_auth_header(credentials: &str) -> HeaderValue {
+    HeaderValue::from_str(&format!("Basic {}", base64::encode(credentials))).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    fn a_cookie_file(name: &str, content: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("{}-{}.cookie", name, process::id()));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn user_pass_header_is_basic_auth() {
+        let authenticator = Authenticator::new(Auth::user_pass("bitcoin", "secret")).unwrap();
+
+        assert_eq!(authenticator.header(), "Basic Yml0Y29pbjpzZWNyZXQ=");
+        assert!(!authenticator.refresh());
+    }
+
+    #[test]
+    fn reads_cookie_file() {
+        let path = a_cookie_file("reads_cookie_file", "__cookie__:abcdef\n");
+
+        let authenticator = Authenticator::new(Auth::cookie_file(&path)).unwrap();
+
+        assert_eq!(
+            authenticator.header(),
+            basic_auth_header("__cookie__:abcdef")
+        );
+    }
+
+    #[test]
+    fn refresh_picks_up_new_cookie() {
+        let path = a_cookie_file("refresh_picks_up_new_cookie", "__cookie__:abcdef");
+        let authenticator = Authenticator::new(Auth::cookie_file(&path)).unwrap();
+
+        assert!(!authenticator.refresh());
+
+        fs::write(&path, "__cookie__:123456").unwrap();
+
+        assert!(authenticator.refresh());
+        assert_eq!(
+            authenticator.header(),
+            basic_auth_header("__cookie__:123456")
+        );
+    }
+
+    #[test]
+    fn fails_on_missing_or_invalid_cookie_file() {
+        let path = a_cookie_file("fails_on_invalid_cookie_file", "garbage");
+
+        assert!(Authenticator::new(Auth::cookie_file(&path)).is_err());
+        assert!(Authenticator::new(Auth::cookie_file("/does/not/exist/.cookie")).is_err());
+    }
+}
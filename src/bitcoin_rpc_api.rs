@@ -1,7 +1,6 @@
 use bitcoin::Address;
 use bitcoin::Script;
-use jsonrpc_client::ClientError;
-use jsonrpc_client::RpcError;
+use error::Error;
 use rpc;
 use types::address::AddressInfoResult;
 use BlockHash;
@@ -19,7 +18,7 @@ pub trait BitcoinRpcApi: Send + Sync {
         &self,
         number_of_required_signatures: u32,
         participants: Vec<&Address>,
-    ) -> Result<Result<rpc::MultiSigAddress, RpcError>, ClientError> {
+    ) -> Result<rpc::MultiSigAddress, Error> {
         unimplemented!()
     }
 
@@ -36,30 +35,24 @@ pub trait BitcoinRpcApi: Send + Sync {
         &self,
         inputs: Vec<&rpc::NewTransactionInput>,
         output: &rpc::NewTransactionOutput,
-    ) -> Result<Result<rpc::SerializedRawTransaction, RpcError>, ClientError> {
+    ) -> Result<rpc::SerializedRawTransaction, Error> {
         unimplemented!()
     }
 
     fn decode_rawtransaction(
         &self,
         tx: rpc::SerializedRawTransaction,
-    ) -> Result<Result<rpc::DecodedRawTransaction, RpcError>, ClientError> {
+    ) -> Result<rpc::DecodedRawTransaction, Error> {
         unimplemented!()
     }
 
-    fn decode_script(
-        &self,
-        script: Script,
-    ) -> Result<Result<rpc::DecodedScript, RpcError>, ClientError> {
+    fn decode_script(&self, script: Script) -> Result<rpc::DecodedScript, Error> {
         unimplemented!()
     }
 
     // TODO: disconnectnode
 
-    fn dump_privkey(
-        &self,
-        address: &Address,
-    ) -> Result<Result<rpc::PrivateKey, RpcError>, ClientError> {
+    fn dump_privkey(&self, address: &Address) -> Result<rpc::PrivateKey, Error> {
         unimplemented!()
     }
 
@@ -72,41 +65,32 @@ pub trait BitcoinRpcApi: Send + Sync {
         &self,
         tx: &rpc::SerializedRawTransaction,
         options: &rpc::FundingOptions,
-    ) -> Result<Result<rpc::FundingResult, RpcError>, ClientError> {
+    ) -> Result<rpc::FundingResult, Error> {
         unimplemented!()
     }
 
-    fn generate(
-        &self,
-        number_of_blocks: u32,
-    ) -> Result<Result<Vec<BlockHash>, RpcError>, ClientError> {
+    fn generate(&self, number_of_blocks: u32) -> Result<Vec<BlockHash>, Error> {
         unimplemented!()
     }
 
     // TODO: generatetoaddress
     // TODO: getaddednodeinfo
 
-    fn get_address_info(
-        &self,
-        address: &Address,
-    ) -> Result<Result<AddressInfoResult, RpcError>, ClientError> {
+    fn get_address_info(&self, address: &Address) -> Result<AddressInfoResult, Error> {
         unimplemented!()
     }
 
     // TODO: getaddressesbylabel
 
-    fn get_balance(&self) -> Result<Result<f32, RpcError>, ClientError> {
+    fn get_balance(&self) -> Result<rpc::Amount, Error> {
         unimplemented!()
     }
 
-    fn get_best_block_hash(&self) -> Result<Result<BlockHash, RpcError>, ClientError> {
+    fn get_best_block_hash(&self) -> Result<BlockHash, Error> {
         unimplemented!()
     }
 
-    fn get_block(
-        &self,
-        header_hash: &BlockHash,
-    ) -> Result<Result<rpc::Block<TransactionId>, RpcError>, ClientError> {
+    fn get_block(&self, header_hash: &BlockHash) -> Result<rpc::Block<TransactionId>, Error> {
         unimplemented!()
     }
 
@@ -114,19 +98,19 @@ pub trait BitcoinRpcApi: Send + Sync {
     fn get_block_verbose(
         &self,
         header_hash: &BlockHash,
-    ) -> Result<Result<rpc::Block<rpc::DecodedRawTransaction>, RpcError>, ClientError> {
+    ) -> Result<rpc::Block<rpc::DecodedRawTransaction>, Error> {
         unimplemented!()
     }
 
-    fn get_blockchain_info(&self) -> Result<Result<rpc::BlockchainInfo, RpcError>, ClientError> {
+    fn get_blockchain_info(&self) -> Result<rpc::BlockchainInfo, Error> {
         unimplemented!()
     }
 
-    fn get_block_count(&self) -> Result<Result<rpc::BlockHeight, RpcError>, ClientError> {
+    fn get_block_count(&self) -> Result<rpc::BlockHeight, Error> {
         unimplemented!()
     }
 
-    fn get_block_hash(&self, height: u32) -> Result<Result<BlockHash, RpcError>, ClientError> {
+    fn get_block_hash(&self, height: u32) -> Result<BlockHash, Error> {
         unimplemented!()
     }
 
@@ -148,7 +132,7 @@ pub trait BitcoinRpcApi: Send + Sync {
     // TODO: getnetworkhashesps
     // TODO: getnetworkinfo
 
-    fn get_new_address(&self) -> Result<Result<Address, RpcError>, ClientError> {
+    fn get_new_address(&self) -> Result<Address, Error> {
         unimplemented!()
     }
 
@@ -159,14 +143,14 @@ pub trait BitcoinRpcApi: Send + Sync {
     fn get_raw_transaction_serialized(
         &self,
         tx: &TransactionId,
-    ) -> Result<Result<rpc::SerializedRawTransaction, RpcError>, ClientError> {
+    ) -> Result<rpc::SerializedRawTransaction, Error> {
         unimplemented!()
     }
 
     fn get_raw_transaction_verbose(
         &self,
         tx: &TransactionId,
-    ) -> Result<Result<rpc::VerboseRawTransaction, RpcError>, ClientError> {
+    ) -> Result<rpc::VerboseRawTransaction, Error> {
         unimplemented!()
     }
 
@@ -199,7 +183,7 @@ pub trait BitcoinRpcApi: Send + Sync {
         min_confirmations: rpc::TxOutConfirmations,
         max_confirmations: Option<u32>,
         recipients: Option<Vec<Address>>,
-    ) -> Result<Result<Vec<rpc::UnspentTransactionOutput>, RpcError>, ClientError> {
+    ) -> Result<Vec<rpc::UnspentTransactionOutput>, Error> {
         unimplemented!()
     }
 
@@ -214,15 +198,15 @@ pub trait BitcoinRpcApi: Send + Sync {
     fn send_raw_transaction(
         &self,
         tx_data: rpc::SerializedRawTransaction,
-    ) -> Result<Result<TransactionId, RpcError>, ClientError> {
+    ) -> Result<TransactionId, Error> {
         unimplemented!()
     }
 
     fn send_to_address(
         &self,
         address: &Address,
-        amount: f64,
-    ) -> Result<Result<TransactionId, RpcError>, ClientError> {
+        amount: rpc::Amount,
+    ) -> Result<TransactionId, Error> {
         unimplemented!()
     }
     // TODO: setlabel
@@ -239,7 +223,7 @@ pub trait BitcoinRpcApi: Send + Sync {
         private_keys: Option<Vec<&rpc::PrivateKey>>,
         dependencies: Option<Vec<&rpc::TransactionOutputDetail>>,
         signature_hash_type: Option<rpc::SigHashType>,
-    ) -> Result<Result<rpc::SigningResult, RpcError>, ClientError> {
+    ) -> Result<rpc::SigningResult, Error> {
         unimplemented!()
     }
 
@@ -247,10 +231,7 @@ pub trait BitcoinRpcApi: Send + Sync {
     // TODO: stop
     // TODO: submitblock
 
-    fn validate_address(
-        &self,
-        address: &Address,
-    ) -> Result<Result<rpc::AddressValidationResult, RpcError>, ClientError> {
+    fn validate_address(&self, address: &Address) -> Result<rpc::AddressValidationResult, Error> {
         unimplemented!()
     }
 
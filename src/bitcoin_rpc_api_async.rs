@@ -0,0 +1,149 @@
+use bitcoin::Address;
+use bitcoin::Script;
+use error::Error;
+use futures::Future;
+use rpc;
+use types::address::AddressInfoResult;
+use BlockHash;
+use TransactionId;
+
+/// The future returned by every method of `AsyncBitcoinRpcApi`.
+///
+/// It resolves to the same result as the blocking `BitcoinRpcApi`.
+pub type RpcFuture<T> = Box<dyn Future<Item = T, Error = Error> + Send>;
+
+/// Non-blocking counterpart of `BitcoinRpcApi`.
+#[allow(unused_variables)]
+pub trait AsyncBitcoinRpcApi: Send + Sync {
+    // Order as per: https://bitcoin.org/en/developer-reference#rpcs
+
+    fn add_multisig_address(
+        &self,
+        number_of_required_signatures: u32,
+        participants: Vec<&Address>,
+    ) -> RpcFuture<rpc::MultiSigAddress> {
+        unimplemented!()
+    }
+
+    fn create_raw_transaction(
+        &self,
+        inputs: Vec<&rpc::NewTransactionInput>,
+        output: &rpc::NewTransactionOutput,
+    ) -> RpcFuture<rpc::SerializedRawTransaction> {
+        unimplemented!()
+    }
+
+    fn decode_rawtransaction(
+        &self,
+        tx: rpc::SerializedRawTransaction,
+    ) -> RpcFuture<rpc::DecodedRawTransaction> {
+        unimplemented!()
+    }
+
+    fn decode_script(&self, script: Script) -> RpcFuture<rpc::DecodedScript> {
+        unimplemented!()
+    }
+
+    fn dump_privkey(&self, address: &Address) -> RpcFuture<rpc::PrivateKey> {
+        unimplemented!()
+    }
+
+    fn fund_raw_transaction(
+        &self,
+        tx: &rpc::SerializedRawTransaction,
+        options: &rpc::FundingOptions,
+    ) -> RpcFuture<rpc::FundingResult> {
+        unimplemented!()
+    }
+
+    fn generate(&self, number_of_blocks: u32) -> RpcFuture<Vec<BlockHash>> {
+        unimplemented!()
+    }
+
+    fn get_address_info(&self, address: &Address) -> RpcFuture<AddressInfoResult> {
+        unimplemented!()
+    }
+
+    fn get_balance(&self) -> RpcFuture<rpc::Amount> {
+        unimplemented!()
+    }
+
+    fn get_best_block_hash(&self) -> RpcFuture<BlockHash> {
+        unimplemented!()
+    }
+
+    fn get_block(&self, header_hash: &BlockHash) -> RpcFuture<rpc::Block<TransactionId>> {
+        unimplemented!()
+    }
+
+    fn get_block_verbose(
+        &self,
+        header_hash: &BlockHash,
+    ) -> RpcFuture<rpc::Block<rpc::DecodedRawTransaction>> {
+        unimplemented!()
+    }
+
+    fn get_blockchain_info(&self) -> RpcFuture<rpc::BlockchainInfo> {
+        unimplemented!()
+    }
+
+    fn get_block_count(&self) -> RpcFuture<rpc::BlockHeight> {
+        unimplemented!()
+    }
+
+    fn get_block_hash(&self, height: u32) -> RpcFuture<BlockHash> {
+        unimplemented!()
+    }
+
+    fn get_new_address(&self) -> RpcFuture<Address> {
+        unimplemented!()
+    }
+
+    fn get_raw_transaction_serialized(
+        &self,
+        tx: &TransactionId,
+    ) -> RpcFuture<rpc::SerializedRawTransaction> {
+        unimplemented!()
+    }
+
+    fn get_raw_transaction_verbose(
+        &self,
+        tx: &TransactionId,
+    ) -> RpcFuture<rpc::VerboseRawTransaction> {
+        unimplemented!()
+    }
+
+    fn list_unspent(
+        &self,
+        min_confirmations: rpc::TxOutConfirmations,
+        max_confirmations: Option<u32>,
+        recipients: Option<Vec<Address>>,
+    ) -> RpcFuture<Vec<rpc::UnspentTransactionOutput>> {
+        unimplemented!()
+    }
+
+    fn send_raw_transaction(
+        &self,
+        tx_data: rpc::SerializedRawTransaction,
+    ) -> RpcFuture<TransactionId> {
+        unimplemented!()
+    }
+
+    fn send_to_address(&self, address: &Address, amount: rpc::Amount) -> RpcFuture<TransactionId> {
+        unimplemented!()
+    }
+
+    fn sign_raw_transaction_with_key(
+        &self,
+        tx: &rpc::SerializedRawTransaction,
+        private_keys: Option<Vec<&rpc::PrivateKey>>,
+        dependencies: Option<Vec<&rpc::TransactionOutputDetail>>,
+        signature_hash_type: Option<rpc::SigHashType>,
+    ) -> RpcFuture<rpc::SigningResult> {
+        unimplemented!()
+    }
+
+    fn validate_address(&self, address: &Address) -> RpcFuture<rpc::AddressValidationResult> {
+        unimplemented!()
+    }
+}
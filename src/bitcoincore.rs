@@ -1,72 +1,264 @@
-use base64;
+use auth::{Auth, Authenticator};
 use bitcoin::Address;
+use bitcoin::Network;
 use bitcoin::Script;
+use config::{ConfigError, RpcConfig};
+use error::Error;
+#[cfg(feature = "test-support")]
+use faults::Faults;
+use fixture::Fixture;
 use jsonrpc_client::{
-    header::{HeaderMap, HeaderValue, AUTHORIZATION},
-    ClientError, HTTPClient, JsonRpcVersion, RpcClient, RpcError, RpcRequest,
+    header::{AUTHORIZATION, CONTENT_TYPE},
+    HTTPClient, JsonRpcVersion, RpcRequest, RpcResponse,
 };
+use payload::{ids, methods};
+use reqwest::{Response, StatusCode};
+use retry::{is_warming_up, Backoff, RetryPolicy};
 use rpc;
-use serde::{de::DeserializeOwned, ser::Serialize};
-use std::fmt::Debug;
+use serde::{
+    de::{self, DeserializeOwned},
+    ser::Serialize,
+};
+use serde_json::{self, json, Value};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    io,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+use timeouts::Timeouts;
 use types::address::AddressInfoResult;
+use version::{NetworkInfo, NodeVersion};
+use wallet::{targets_wallet, wallet_url};
 use BitcoinRpcApi;
 use BlockHash;
 use TransactionId;
 
-struct RetryConfig {
-    max_retries: u32,
-    interval: u64,
-}
-
 #[deprecated(
     since = "0.6.1",
     note = "This library is deprecated in favor of bitcoincore-rpc."
 )]
 pub struct BitcoinCoreClient {
-    client: RpcClient,
-    retry_config: Option<RetryConfig>,
+    clients: Arc<Mutex<HashMap<Option<Duration>, HTTPClient>>>,
+    timeouts: Timeouts,
+    url: String,
+    wallet_url: Option<String>,
+    auth: Arc<Authenticator>,
+    retry_policy: RetryPolicy,
+    next_id: Arc<AtomicUsize>,
+    node_version: Arc<Mutex<Option<NodeVersion>>>,
+    fixture: Option<Arc<Fixture>>,
+    #[cfg(feature = "test-support")]
+    faults: Option<Arc<Faults>>,
 }
 
 #[allow(dead_code)]
 impl BitcoinCoreClient {
     pub fn new(url: &str, username: &str, password: &str) -> Self {
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            AUTHORIZATION,
-            HeaderValue::from_str(&format!(
-                "Basic {}",
-                base64::encode(&format!("{}:{}", username, password))
-            ))
-            .unwrap(),
-        );
+        Self::with_auth(url, Auth::user_pass(username, password))
+            .expect("username and password authentication cannot fail")
+    }
 
-        let client = HTTPClient::builder()
-            .default_headers(headers)
-            .build()
-            .expect("unable to create HTTP client");
+    /// Authenticates with the cookie bitcoind writes to `<datadir>/.cookie`.
+    ///
+    /// The cookie file is re-read whenever the node rejects the credentials, e.g. after a restart.
+    pub fn from_cookie_file<P: AsRef<Path>>(url: &str, cookie_file: P) -> io::Result<Self> {
+        Self::with_auth(url, Auth::cookie_file(cookie_file))
+    }
+
+    /// Reads the RPC settings for `network` from `<datadir>/bitcoin.conf`.
+    pub fn from_datadir<P: AsRef<Path>>(datadir: P, network: Network) -> Result<Self, ConfigError> {
+        let config = RpcConfig::from_datadir(datadir, network)?;
 
-        let rpc_client = RpcClient::new(client, url);
+        Ok(Self::from_config(&config)?)
+    }
+
+    pub fn from_config(config: &RpcConfig) -> io::Result<Self> {
+        Self::with_auth(&config.url, config.auth.clone())
+    }
 
+    pub fn with_auth(url: &str, auth: Auth) -> io::Result<Self> {
+        Ok(BitcoinCoreClient {
+            clients: Arc::new(Mutex::new(HashMap::new())),
+            timeouts: Timeouts::default(),
+            url: url.to_string(),
+            wallet_url: None,
+            auth: Arc::new(Authenticator::new(auth)?),
+            retry_policy: RetryPolicy::default(),
+            next_id: Arc::new(AtomicUsize::new(1)),
+            node_version: Arc::new(Mutex::new(None)),
+            fixture: None,
+            #[cfg(feature = "test-support")]
+            faults: None,
+        })
+    }
+
+    /// Answers all requests from a fixture file written by a client `with_recording`, without
+    /// talking to a node.
+    ///
+    /// Requests that were not recorded fail with `Error::UnexpectedRequest`.
+    pub fn from_fixture<P: AsRef<Path>>(fixture_file: P) -> io::Result<Self> {
+        Ok(BitcoinCoreClient {
+            fixture: Some(Arc::new(Fixture::replaying(fixture_file)?)),
+            ..Self::new("http://localhost", "", "").with_retry_policy(RetryPolicy::none())
+        })
+    }
+
+    pub fn with_retry_policy(self, retry_policy: RetryPolicy) -> Self {
+        BitcoinCoreClient {
+            retry_policy,
+            ..self
+        }
+    }
+
+    /// Writes every request and the node's response to `fixture_file`, to be replayed with
+    /// `from_fixture`.
+    pub fn with_recording<P: AsRef<Path>>(self, fixture_file: P) -> Self {
         BitcoinCoreClient {
-            client: rpc_client,
-            retry_config: Some(RetryConfig {
-                max_retries: 10,
-                interval: 500,
-            }),
+            fixture: Some(Arc::new(Fixture::recording(fixture_file))),
+            ..self
         }
     }
 
-    fn get_raw_transaction<R: Debug>(
+    /// Fails requests like a flaky node would, to test how the retry logic and the
+    /// application cope with it.
+    ///
+    /// Faults are injected into every attempt, so retried requests may fail again.
+    #[cfg(feature = "test-support")]
+    pub fn with_faults(self, faults: Faults) -> Self {
+        BitcoinCoreClient {
+            faults: Some(Arc::new(faults)),
+            ..self
+        }
+    }
+
+    pub fn with_timeouts(self, timeouts: Timeouts) -> Self {
+        BitcoinCoreClient {
+            clients: Arc::new(Mutex::new(HashMap::new())),
+            timeouts,
+            ..self
+        }
+    }
+
+    /// Returns a handle that sends wallet RPCs to the endpoint of wallet `name`.
+    ///
+    /// All other RPCs still go to the node. The handle shares the connection pool and
+    /// credentials with this client.
+    pub fn wallet(&self, name: &str) -> Self {
+        BitcoinCoreClient {
+            clients: self.clients.clone(),
+            timeouts: self.timeouts.clone(),
+            url: self.url.clone(),
+            wallet_url: Some(wallet_url(&self.url, name)),
+            auth: self.auth.clone(),
+            retry_policy: self.retry_policy,
+            next_id: self.next_id.clone(),
+            node_version: self.node_version.clone(),
+            fixture: self.fixture.clone(),
+            #[cfg(feature = "test-support")]
+            faults: self.faults.clone(),
+        }
+    }
+
+    /// Returns a new request id, increasing with every call.
+    ///
+    /// Ids are shared with all wallet handles of this client.
+    pub fn next_id(&self) -> String {
+        self.next_id.fetch_add(1, Ordering::Relaxed).to_string()
+    }
+
+    /// The version of Bitcoin Core the node runs.
+    ///
+    /// It is queried on first use and then cached, methods that differ between versions of
+    /// Bitcoin Core use it to pick the RPC the node understands.
+    pub fn node_version(&self) -> Result<NodeVersion, Error> {
+        if let Some(version) = *self.node_version.lock().unwrap() {
+            return Ok(version);
+        }
+
+        let version = NodeVersion(self.call::<NetworkInfo>("getnetworkinfo", &[])?.version);
+        *self.node_version.lock().unwrap() = Some(version);
+
+        Ok(version)
+    }
+
+    fn require_address_types(&self, method: &str) -> Result<(), Error> {
+        let version = self.node_version()?;
+
+        if version.has_address_types() {
+            Ok(())
+        } else {
+            Err(Error::UnsupportedByNodeVersion {
+                method: method.to_string(),
+                version,
+            })
+        }
+    }
+
+    /// Sends all requests to the node in a single HTTP round-trip.
+    ///
+    /// The requests share their params type `P` and result type `R`, e.g. `getblockhash` for a
+    /// range of heights. The results are returned in the same order as the requests. An
+    /// `RpcError` or a result that doesn't deserialize into `R` only fails the request it belongs
+    /// to, any other `Error` fails the whole batch.
+    pub fn send_batch<R: DeserializeOwned + Debug, P: Serialize + Debug>(
+        &self,
+        requests: &[RpcRequest<P>],
+    ) -> Result<Vec<Result<R, Error>>, Error> {
+        if requests.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let payload = serde_json::to_value(requests).map_err(Error::Json)?;
+        let responses = self.execute(&payload, |responses: &Vec<RpcResponse<Value>>| {
+            responses
+                .iter()
+                .filter_map(|response| response.error.as_ref())
+                .any(is_warming_up)
+        })?;
+
+        Ok(correlate(&ids(&payload), responses)?
+            .into_iter()
+            .zip(methods(&payload))
+            .map(|(response, method)| match response {
+                RpcResponse {
+                    error: Some(rpc_error),
+                    ..
+                } => Err(Error::Rpc(rpc_error)),
+                RpcResponse { result, .. } => deserialize_response(
+                    method,
+                    result.unwrap_or(Value::Null).to_string().as_bytes(),
+                ),
+            })
+            .collect())
+    }
+
+    /// Sends an arbitrary RPC, e.g. one `BitcoinRpcApi` does not cover yet.
+    ///
+    /// The request goes through the same authentication, retry and wallet routing as all other
+    /// RPCs.
+    pub fn call<R: DeserializeOwned + Debug>(
+        &self,
+        method: &str,
+        params: &[Value],
+    ) -> Result<R, Error> {
+        self.send_payload(&raw_request(&self.next_id(), method, params))
+    }
+
+    fn get_raw_transaction<R: DeserializeOwned + Debug>(
         &self,
         tx: &TransactionId,
         verbose: bool,
-    ) -> Result<Result<R, RpcError>, ClientError>
-    where
-        R: DeserializeOwned,
-    {
+    ) -> Result<R, Error> {
         self.send(&RpcRequest::new2(
             JsonRpcVersion::V1,
-            "42",
+            &self.next_id(),
             "getrawtransaction",
             tx,
             verbose,
@@ -81,10 +273,10 @@ impl BitcoinRpcApi for BitcoinCoreClient {
         &self,
         number_of_required_signatures: u32,
         participants: Vec<&Address>,
-    ) -> Result<Result<rpc::MultiSigAddress, RpcError>, ClientError> {
+    ) -> Result<rpc::MultiSigAddress, Error> {
         self.send(&RpcRequest::new2(
             JsonRpcVersion::V1,
-            "42",
+            &self.next_id(),
             "addmultisigaddress",
             number_of_required_signatures,
             participants,
@@ -95,10 +287,10 @@ impl BitcoinRpcApi for BitcoinCoreClient {
         &self,
         inputs: Vec<&rpc::NewTransactionInput>,
         output: &rpc::NewTransactionOutput,
-    ) -> Result<Result<rpc::SerializedRawTransaction, RpcError>, ClientError> {
+    ) -> Result<rpc::SerializedRawTransaction, Error> {
         self.send(&RpcRequest::new2(
             JsonRpcVersion::V1,
-            "42",
+            &self.next_id(),
             "createrawtransaction",
             inputs,
             output,
@@ -108,34 +300,28 @@ impl BitcoinRpcApi for BitcoinCoreClient {
     fn decode_rawtransaction(
         &self,
         tx: rpc::SerializedRawTransaction,
-    ) -> Result<Result<rpc::DecodedRawTransaction, RpcError>, ClientError> {
+    ) -> Result<rpc::DecodedRawTransaction, Error> {
         self.send(&RpcRequest::new1(
             JsonRpcVersion::V1,
-            "42",
+            &self.next_id(),
             "decoderawtransaction",
             tx,
         ))
     }
 
-    fn decode_script(
-        &self,
-        script: Script,
-    ) -> Result<Result<rpc::DecodedScript, RpcError>, ClientError> {
+    fn decode_script(&self, script: Script) -> Result<rpc::DecodedScript, Error> {
         self.send(&RpcRequest::new1(
             JsonRpcVersion::V1,
-            "42",
+            &self.next_id(),
             "decodescript",
             script,
         ))
     }
 
-    fn dump_privkey(
-        &self,
-        address: &Address,
-    ) -> Result<Result<rpc::PrivateKey, RpcError>, ClientError> {
+    fn dump_privkey(&self, address: &Address) -> Result<rpc::PrivateKey, Error> {
         self.send(&RpcRequest::new1(
             JsonRpcVersion::V1,
-            "42",
+            &self.next_id(),
             "dumpprivkey",
             address,
         ))
@@ -145,59 +331,66 @@ impl BitcoinRpcApi for BitcoinCoreClient {
         &self,
         tx: &rpc::SerializedRawTransaction,
         options: &rpc::FundingOptions,
-    ) -> Result<Result<rpc::FundingResult, RpcError>, ClientError> {
+    ) -> Result<rpc::FundingResult, Error> {
         self.send(&RpcRequest::new2(
             JsonRpcVersion::V1,
-            "42",
+            &self.next_id(),
             "fundrawtransaction",
             tx,
             options,
         ))
     }
 
-    fn generate(
-        &self,
-        number_of_blocks: u32,
-    ) -> Result<Result<Vec<BlockHash>, RpcError>, ClientError> {
-        self.send(&RpcRequest::new1(
+    fn generate(&self, number_of_blocks: u32) -> Result<Vec<BlockHash>, Error> {
+        if self.node_version()?.has_generate() {
+            return self.send(&RpcRequest::new1(
+                JsonRpcVersion::V1,
+                &self.next_id(),
+                "generate",
+                number_of_blocks,
+            ));
+        }
+
+        let address = self.get_new_address()?;
+        self.send(&RpcRequest::new2(
             JsonRpcVersion::V1,
-            "42",
-            "generate",
+            &self.next_id(),
+            "generatetoaddress",
             number_of_blocks,
+            address,
         ))
     }
 
-    fn get_address_info(
-        &self,
-        address: &Address,
-    ) -> Result<Result<AddressInfoResult, RpcError>, ClientError> {
+    fn get_address_info(&self, address: &Address) -> Result<AddressInfoResult, Error> {
+        self.require_address_types("getaddressinfo")?;
         self.send(&RpcRequest::new1(
             JsonRpcVersion::V1,
-            "42",
+            &self.next_id(),
             "getaddressinfo",
             address,
         ))
     }
 
-    fn get_balance(&self) -> Result<Result<f32, RpcError>, ClientError> {
-        self.send(&RpcRequest::new0(JsonRpcVersion::V1, "42", "getbalance"))
+    fn get_balance(&self) -> Result<rpc::Amount, Error> {
+        self.send(&RpcRequest::new0(
+            JsonRpcVersion::V1,
+            &self.next_id(),
+            "getbalance",
+        ))
     }
 
-    fn get_best_block_hash(&self) -> Result<Result<BlockHash, RpcError>, ClientError> {
+    fn get_best_block_hash(&self) -> Result<BlockHash, Error> {
         self.send(&RpcRequest::new0(
             JsonRpcVersion::V1,
-            "42",
+            &self.next_id(),
             "getbestblockhash",
         ))
     }
 
-    fn get_block(
-        &self,
-        header_hash: &BlockHash,
-    ) -> Result<Result<rpc::Block<TransactionId>, RpcError>, ClientError> {
+    fn get_block(&self, header_hash: &BlockHash) -> Result<rpc::Block<TransactionId>, Error> {
         self.send(&RpcRequest::new1(
             JsonRpcVersion::V1,
-            "42",
+            &self.next_id(),
             "getblock",
             header_hash,
         ))
@@ -206,41 +399,46 @@ impl BitcoinRpcApi for BitcoinCoreClient {
     fn get_block_verbose(
         &self,
         header_hash: &BlockHash,
-    ) -> Result<Result<rpc::Block<rpc::DecodedRawTransaction>, RpcError>, ClientError> {
+    ) -> Result<rpc::Block<rpc::DecodedRawTransaction>, Error> {
         self.send(&RpcRequest::new2(
             JsonRpcVersion::V1,
-            "42",
+            &self.next_id(),
             "getblock",
             header_hash,
             2,
         ))
     }
 
-    fn get_blockchain_info(&self) -> Result<Result<rpc::BlockchainInfo, RpcError>, ClientError> {
+    fn get_blockchain_info(&self) -> Result<rpc::BlockchainInfo, Error> {
         self.send(&RpcRequest::new0(
             JsonRpcVersion::V1,
-            "42",
+            &self.next_id(),
             "getblockchaininfo",
         ))
     }
 
-    fn get_block_count(&self) -> Result<Result<rpc::BlockHeight, RpcError>, ClientError> {
-        self.send(&RpcRequest::new0(JsonRpcVersion::V1, "42", "getblockcount"))
+    fn get_block_count(&self) -> Result<rpc::BlockHeight, Error> {
+        self.send(&RpcRequest::new0(
+            JsonRpcVersion::V1,
+            &self.next_id(),
+            "getblockcount",
+        ))
     }
 
-    fn get_block_hash(&self, height: u32) -> Result<Result<BlockHash, RpcError>, ClientError> {
+    fn get_block_hash(&self, height: u32) -> Result<BlockHash, Error> {
         self.send(&RpcRequest::new1(
             JsonRpcVersion::V1,
-            "42",
+            &self.next_id(),
             "getblockhash",
             height,
         ))
     }
 
-    fn get_new_address(&self) -> Result<Result<Address, RpcError>, ClientError> {
+    fn get_new_address(&self) -> Result<Address, Error> {
+        self.require_address_types("getnewaddress")?;
         self.send(&RpcRequest::new2(
             JsonRpcVersion::V1,
-            "42",
+            &self.next_id(),
             "getnewaddress",
             "",
             "bech32",
@@ -250,14 +448,14 @@ impl BitcoinRpcApi for BitcoinCoreClient {
     fn get_raw_transaction_serialized(
         &self,
         tx: &TransactionId,
-    ) -> Result<Result<rpc::SerializedRawTransaction, RpcError>, ClientError> {
+    ) -> Result<rpc::SerializedRawTransaction, Error> {
         self.get_raw_transaction(tx, false)
     }
 
     fn get_raw_transaction_verbose(
         &self,
         tx: &TransactionId,
-    ) -> Result<Result<rpc::VerboseRawTransaction, RpcError>, ClientError> {
+    ) -> Result<rpc::VerboseRawTransaction, Error> {
         self.get_raw_transaction(tx, true)
     }
 
@@ -266,17 +464,10 @@ impl BitcoinRpcApi for BitcoinCoreClient {
         min_confirmations: rpc::TxOutConfirmations,
         max_confirmations: Option<u32>,
         recipients: Option<Vec<Address>>,
-    ) -> Result<Result<Vec<rpc::UnspentTransactionOutput>, RpcError>, ClientError> {
-        use rpc::TxOutConfirmations::*;
-
-        let min_confirmations = match min_confirmations {
-            Unconfirmed => 0,
-            AtLeast(number) => number,
-        };
-
+    ) -> Result<Vec<rpc::UnspentTransactionOutput>, Error> {
         self.send(&RpcRequest::new3(
             JsonRpcVersion::V1,
-            "42",
+            &self.next_id(),
             "listunspent",
             min_confirmations,
             max_confirmations,
@@ -287,10 +478,10 @@ impl BitcoinRpcApi for BitcoinCoreClient {
     fn send_raw_transaction(
         &self,
         tx_data: rpc::SerializedRawTransaction,
-    ) -> Result<Result<TransactionId, RpcError>, ClientError> {
+    ) -> Result<TransactionId, Error> {
         self.send(&RpcRequest::new1(
             JsonRpcVersion::V1,
-            "42",
+            &self.next_id(),
             "sendrawtransaction",
             tx_data,
         ))
@@ -299,11 +490,11 @@ impl BitcoinRpcApi for BitcoinCoreClient {
     fn send_to_address(
         &self,
         address: &Address,
-        amount: f64,
-    ) -> Result<Result<TransactionId, RpcError>, ClientError> {
+        amount: rpc::Amount,
+    ) -> Result<TransactionId, Error> {
         self.send(&RpcRequest::new2(
             JsonRpcVersion::V1,
-            "42",
+            &self.next_id(),
             "sendtoaddress",
             address,
             amount,
@@ -316,25 +507,35 @@ impl BitcoinRpcApi for BitcoinCoreClient {
         private_keys: Option<Vec<&rpc::PrivateKey>>,
         dependencies: Option<Vec<&rpc::TransactionOutputDetail>>,
         signature_hash_type: Option<rpc::SigHashType>,
-    ) -> Result<Result<rpc::SigningResult, RpcError>, ClientError> {
+    ) -> Result<rpc::SigningResult, Error> {
+        if self.node_version()?.has_sign_raw_transaction_with_key() {
+            return self.send(&RpcRequest::new4(
+                JsonRpcVersion::V1,
+                &self.next_id(),
+                "signrawtransactionwithkey",
+                tx,
+                private_keys,
+                dependencies,
+                signature_hash_type,
+            ));
+        }
+
+        // Same result, but the dependencies come before the keys
         self.send(&RpcRequest::new4(
             JsonRpcVersion::V1,
-            "42",
-            "signrawtransactionwithkey",
+            &self.next_id(),
+            "signrawtransaction",
             tx,
-            private_keys,
             dependencies,
+            private_keys,
             signature_hash_type,
         ))
     }
 
-    fn validate_address(
-        &self,
-        address: &Address,
-    ) -> Result<Result<rpc::AddressValidationResult, RpcError>, ClientError> {
+    fn validate_address(&self, address: &Address) -> Result<rpc::AddressValidationResult, Error> {
         self.send(&RpcRequest::new1(
             JsonRpcVersion::V1,
-            "42",
+            &self.next_id(),
             "validateaddress",
             address,
         ))
@@ -345,22 +546,337 @@ impl BitcoinCoreClient {
     fn send<R: DeserializeOwned + Debug, P: Serialize + Debug>(
         &self,
         request: &RpcRequest<P>,
-    ) -> Result<Result<R, RpcError>, ClientError> {
-        if let Some(ref config) = self.retry_config {
-            for i in 0..config.max_retries {
-                let result = self.client.send::<R, P>(request);
-
-                match result {
-                    Ok(Err(ref rpc_error)) if rpc_error.code == -28 => {
-                        info!("Bitcoind is still starting up. Request will be retried in {} milliseconds. ({}/{}) ", config.interval, i, config.max_retries);
-
-                        ::std::thread::sleep(::std::time::Duration::from_millis(config.interval));
-                        continue;
-                    }
-                    _ => return result,
-                }
+    ) -> Result<R, Error> {
+        let payload = serde_json::to_value(request).map_err(Error::Json)?;
+
+        self.send_payload(&payload)
+    }
+
+    fn send_payload<R: DeserializeOwned + Debug>(&self, payload: &Value) -> Result<R, Error> {
+        self.execute(payload, |response: &RpcResponse<R>| match response.error {
+            Some(ref rpc_error) => is_warming_up(rpc_error),
+            None => false,
+        })
+        .and_then(|response| verify_id(ids(payload)[0], response))
+        .and_then(|response| response.into_result().map_err(Error::Rpc))
+    }
+
+    fn execute<R: DeserializeOwned, W: Fn(&R) -> bool>(
+        &self,
+        payload: &Value,
+        warming_up: W,
+    ) -> Result<R, Error> {
+        let url = match self.wallet_url {
+            Some(ref wallet_url) if targets_wallet(payload) => wallet_url,
+            _ => &self.url,
+        };
+        let client = self.http_client(self.timeouts.read(payload));
+        let mut backoff = Backoff::new(self.retry_policy);
+        let methods = methods(payload);
+        let payload = payload.to_string();
+
+        loop {
+            let outcome = self.post(&client, url, &methods, &payload);
+
+            match backoff.next_delay(&outcome, &warming_up) {
+                Some(delay) => thread::sleep(delay),
+                None => return outcome,
+            }
+        }
+    }
+
+    /// The HTTP clients are built on first use since most applications never send requests
+    /// with an overridden read timeout.
+    fn http_client(&self, read_timeout: Option<Duration>) -> HTTPClient {
+        let mut clients = self.clients.lock().unwrap();
+
+        clients
+            .entry(read_timeout)
+            .or_insert_with(|| {
+                HTTPClient::builder()
+                    .connect_timeout(self.timeouts.connect())
+                    .timeout(read_timeout)
+                    .build()
+                    .expect("unable to create HTTP client")
+            })
+            .clone()
+    }
+
+    fn post<R: DeserializeOwned>(
+        &self,
+        client: &HTTPClient,
+        url: &str,
+        methods: &[&str],
+        payload: &str,
+    ) -> Result<R, Error> {
+        trace!(">>> {}", payload);
+
+        let method = methods.join(", ");
+
+        #[cfg(feature = "test-support")]
+        {
+            if let Some(fault) = self
+                .faults
+                .as_ref()
+                .and_then(|faults| faults.next(methods.iter().cloned()))
+            {
+                debug!("Injecting {:?} into request {}", fault, payload);
+                return fault.into_response(&method, payload);
+            }
+        }
+
+        let (status, body) = match self.fixture.as_ref().and_then(|f| f.replay(payload)) {
+            Some(replayed) => replayed?,
+            None => self.post_http(client, url, payload)?,
+        };
+        trace!("<<< {} {}", status, body);
+
+        if let Some(ref fixture) = self.fixture {
+            fixture.record(payload, status, &body)?;
+        }
+
+        if is_http_error(status) {
+            return Err(Error::HttpStatus(status));
+        }
+
+        deserialize_response(&method, body.as_bytes())
+    }
+
+    fn post_http(
+        &self,
+        client: &HTTPClient,
+        url: &str,
+        payload: &str,
+    ) -> Result<(StatusCode, String), Error> {
+        let mut response = self.post_authenticated(client, url, payload)?;
+
+        if response.status() == StatusCode::UNAUTHORIZED && self.auth.refresh() {
+            response = self.post_authenticated(client, url, payload)?;
+        }
+
+        Ok((response.status(), response.text()?))
+    }
+
+    fn post_authenticated(
+        &self,
+        client: &HTTPClient,
+        url: &str,
+        payload: &str,
+    ) -> Result<Response, Error> {
+        client
+            .post(url)
+            .header(AUTHORIZATION, self.auth.header())
+            .header(CONTENT_TYPE, "application/json")
+            .body(payload.to_string())
+            .send()
+            .map_err(Error::from)
+    }
+}
+
+/// A serialized request with an arbitrary number of parameters.
+pub(crate) fn raw_request(id: &str, method: &str, params: &[Value]) -> Value {
+    json!({
+        "jsonrpc": JsonRpcVersion::V1,
+        "id": id,
+        "method": method,
+        "params": params,
+    })
+}
+
+pub(crate) fn verify_id<R>(
+    expected: &str,
+    response: RpcResponse<R>,
+) -> Result<RpcResponse<R>, Error> {
+    if response.id != expected {
+        return Err(Error::IdMismatch {
+            expected: expected.to_string(),
+            actual: response.id,
+        });
+    }
+
+    Ok(response)
+}
+
+/// Orders the responses to a batch like the requests with the given ids.
+///
+/// bitcoind may answer the requests of a batch in any order.
+fn correlate<R>(
+    ids: &[&str],
+    responses: Vec<RpcResponse<R>>,
+) -> Result<Vec<RpcResponse<R>>, Error> {
+    if responses.len() != ids.len() {
+        return Err(Error::Json(de::Error::custom(format!(
+            "expected {} responses in batch but got {}",
+            ids.len(),
+            responses.len()
+        ))));
+    }
+
+    let mut slots: Vec<Option<RpcResponse<R>>> = ids.iter().map(|_| None).collect();
+
+    for response in responses {
+        let slot = ids
+            .iter()
+            .zip(slots.iter_mut())
+            .find(|&(id, ref slot)| *id == response.id && slot.is_none())
+            .map(|(_, slot)| slot);
+
+        match slot {
+            Some(slot) => *slot = Some(response),
+            None => {
+                return Err(Error::IdMismatch {
+                    expected: ids.join(", "),
+                    actual: response.id,
+                })
+            }
+        }
+    }
+
+    Ok(slots.into_iter().map(Option::unwrap).collect())
+}
+
+/// How much of a response body is kept in a deserialization error.
+const MAX_RESPONSE_EXCERPT: usize = 512;
+
+/// Deserializes a response body, reporting the method, the path to the offending field and the
+/// beginning of the body on failure.
+pub(crate) fn deserialize_response<R: DeserializeOwned>(
+    method: &str,
+    body: &[u8],
+) -> Result<R, Error> {
+    let mut deserializer = serde_json::Deserializer::from_slice(body);
+
+    serde_path_to_error::deserialize(&mut deserializer)
+        .map_err(|e| (e.path().to_string(), e.into_inner()))
+        .and_then(|response| {
+            deserializer
+                .end()
+                .map(|()| response)
+                .map_err(|e| (String::from("."), e))
+        })
+        .map_err(|(path, source)| Error::Deserialization {
+            method: method.to_string(),
+            path,
+            source,
+            response: excerpt(body),
+        })
+}
+
+fn excerpt(body: &[u8]) -> String {
+    let body = String::from_utf8_lossy(body);
+
+    match body.char_indices().nth(MAX_RESPONSE_EXCERPT) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body.into_owned(),
+    }
+}
+
+/// bitcoind answers with a JSON body for every RPC error, except for these status codes.
+pub(crate) fn is_http_error(status: StatusCode) -> bool {
+    status == StatusCode::UNAUTHORIZED
+        || status == StatusCode::FORBIDDEN
+        || status == StatusCode::SERVICE_UNAVAILABLE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn a_response(id: &str, result: u32) -> RpcResponse<u32> {
+        serde_json::from_value(json!({"id": id, "result": result, "error": null})).unwrap()
+    }
+
+    #[test]
+    fn serializes_raw_request_like_rpc_request() {
+        let request = RpcRequest::new2(JsonRpcVersion::V1, "1", "getblockhash", 0, "foo");
+
+        assert_eq!(
+            raw_request("1", "getblockhash", &[json!(0), json!("foo")]),
+            serde_json::to_value(&request).unwrap()
+        );
+        assert_eq!(
+            raw_request("1", "getblockcount", &[]),
+            json!({"jsonrpc": "1.0", "id": "1", "method": "getblockcount", "params": []})
+        );
+    }
+
+    #[test]
+    fn reports_method_and_path_of_deserialization_error() {
+        let body = br#"{"id": "1", "result": {"vin": [{"txid": 42}]}, "error": null}"#;
+
+        #[derive(Debug, Deserialize)]
+        struct Input {
+            #[allow(dead_code)]
+            txid: String,
+        }
+        #[derive(Debug, Deserialize)]
+        struct Tx {
+            #[allow(dead_code)]
+            vin: Vec<Input>,
+        }
+
+        match deserialize_response::<RpcResponse<Tx>>("getrawtransaction", body) {
+            Err(Error::Deserialization {
+                method,
+                path,
+                response,
+                ..
+            }) => {
+                assert_eq!(method, "getrawtransaction");
+                assert_eq!(path, "result.vin[0].txid");
+                assert_eq!(response.as_bytes(), &body[..]);
+            }
+            other => panic!("expected deserialization error but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn truncates_response_in_deserialization_error() {
+        let body = format!("[{}]", "1,".repeat(1000));
+
+        match deserialize_response::<RpcResponse<u32>>("getblockcount", body.as_bytes()) {
+            Err(Error::Deserialization { response, .. }) => {
+                assert_eq!(response.len(), MAX_RESPONSE_EXCERPT + 3);
+                assert!(response.ends_with("..."));
+            }
+            other => panic!("expected deserialization error but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn correlates_batch_responses_by_id() {
+        let responses = vec![
+            a_response("2", 20),
+            a_response("3", 30),
+            a_response("1", 10),
+        ];
+
+        let results = correlate(&["1", "2", "3"], responses)
+            .unwrap()
+            .into_iter()
+            .map(RpcResponse::into_result)
+            .collect::<Vec<_>>();
+
+        assert_eq!(results, vec![Ok(10), Ok(20), Ok(30)]);
+    }
+
+    #[test]
+    fn fails_on_unknown_or_missing_response_id() {
+        match correlate(&["1", "2"], vec![a_response("1", 10), a_response("1", 10)]) {
+            Err(Error::IdMismatch { expected, actual }) => {
+                assert_eq!(expected, "1, 2");
+                assert_eq!(actual, "1");
+            }
+            other => panic!("expected id mismatch but got {:?}", other),
+        }
+
+        match verify_id("7", a_response("42", 10)) {
+            Err(Error::IdMismatch { expected, actual }) => {
+                assert_eq!(expected, "7");
+                assert_eq!(actual, "42");
             }
+            other => panic!("expected id mismatch but got {:?}", other),
         }
-        self.client.send(request)
     }
 }
@@ -0,0 +1,607 @@
+use auth::{Auth, Authenticator};
+use bitcoin::Address;
+use bitcoin::Network;
+use bitcoin::Script;
+use bitcoin_rpc_api_async::{AsyncBitcoinRpcApi, RpcFuture};
+use bitcoincore::{deserialize_response, is_http_error, raw_request, verify_id};
+use config::{ConfigError, RpcConfig};
+use error::Error;
+use futures::{
+    future::{self, Either, Loop},
+    Future, Stream,
+};
+use jsonrpc_client::{
+    header::{AUTHORIZATION, CONTENT_TYPE},
+    JsonRpcVersion, RpcRequest, RpcResponse,
+};
+use payload::{ids, methods};
+use reqwest::{
+    async::{Client as AsyncHTTPClient, Response},
+    StatusCode,
+};
+use retry::{is_warming_up, Backoff, RetryPolicy};
+use rpc;
+use serde::{de::DeserializeOwned, ser::Serialize};
+use serde_json::{self, Value};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    io,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use timeouts::Timeouts;
+use tokio_timer::Delay;
+use types::address::AddressInfoResult;
+use version::{NetworkInfo, NodeVersion};
+use wallet::{targets_wallet, wallet_url};
+use BlockHash;
+use TransactionId;
+
+/// Non-blocking Bitcoin Core client.
+///
+/// The returned futures must be driven by a tokio runtime. Retries wait on a
+/// timer instead of blocking the thread.
+#[derive(Clone)]
+pub struct AsyncBitcoinCoreClient {
+    clients: Arc<Mutex<HashMap<Option<Duration>, AsyncHTTPClient>>>,
+    timeouts: Timeouts,
+    url: String,
+    wallet_url: Option<String>,
+    auth: Arc<Authenticator>,
+    retry_policy: RetryPolicy,
+    next_id: Arc<AtomicUsize>,
+    node_version: Arc<Mutex<Option<NodeVersion>>>,
+}
+
+impl AsyncBitcoinCoreClient {
+    pub fn new(url: &str, username: &str, password: &str) -> Self {
+        Self::with_auth(url, Auth::user_pass(username, password))
+            .expect("username and password authentication cannot fail")
+    }
+
+    /// See `BitcoinCoreClient::from_cookie_file`.
+    pub fn from_cookie_file<P: AsRef<Path>>(url: &str, cookie_file: P) -> io::Result<Self> {
+        Self::with_auth(url, Auth::cookie_file(cookie_file))
+    }
+
+    /// See `BitcoinCoreClient::from_datadir`.
+    pub fn from_datadir<P: AsRef<Path>>(datadir: P, network: Network) -> Result<Self, ConfigError> {
+        let config = RpcConfig::from_datadir(datadir, network)?;
+
+        Ok(Self::from_config(&config)?)
+    }
+
+    pub fn from_config(config: &RpcConfig) -> io::Result<Self> {
+        Self::with_auth(&config.url, config.auth.clone())
+    }
+
+    pub fn with_auth(url: &str, auth: Auth) -> io::Result<Self> {
+        Ok(AsyncBitcoinCoreClient {
+            clients: Arc::new(Mutex::new(HashMap::new())),
+            timeouts: Timeouts::default(),
+            url: url.to_string(),
+            wallet_url: None,
+            auth: Arc::new(Authenticator::new(auth)?),
+            retry_policy: RetryPolicy::default(),
+            next_id: Arc::new(AtomicUsize::new(1)),
+            node_version: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    pub fn with_retry_policy(self, retry_policy: RetryPolicy) -> Self {
+        AsyncBitcoinCoreClient {
+            retry_policy,
+            ..self
+        }
+    }
+
+    pub fn with_timeouts(self, timeouts: Timeouts) -> Self {
+        AsyncBitcoinCoreClient {
+            clients: Arc::new(Mutex::new(HashMap::new())),
+            timeouts,
+            ..self
+        }
+    }
+
+    /// See `BitcoinCoreClient::wallet`.
+    pub fn wallet(&self, name: &str) -> Self {
+        AsyncBitcoinCoreClient {
+            clients: self.clients.clone(),
+            timeouts: self.timeouts.clone(),
+            url: self.url.clone(),
+            wallet_url: Some(wallet_url(&self.url, name)),
+            auth: self.auth.clone(),
+            retry_policy: self.retry_policy,
+            next_id: self.next_id.clone(),
+            node_version: self.node_version.clone(),
+        }
+    }
+
+    /// See `BitcoinCoreClient::next_id`.
+    pub fn next_id(&self) -> String {
+        self.next_id.fetch_add(1, Ordering::Relaxed).to_string()
+    }
+
+    /// See `BitcoinCoreClient::node_version`.
+    pub fn node_version(&self) -> RpcFuture<NodeVersion> {
+        if let Some(version) = *self.node_version.lock().unwrap() {
+            return Box::new(future::ok(version));
+        }
+
+        let node_version = self.node_version.clone();
+        Box::new(
+            self.call::<NetworkInfo>("getnetworkinfo", &[])
+                .map(move |info| {
+                    let version = NodeVersion(info.version);
+                    *node_version.lock().unwrap() = Some(version);
+
+                    version
+                }),
+        )
+    }
+
+    /// Fails with `Error::UnsupportedByNodeVersion` if the node doesn't know address types.
+    fn require_address_types(&self, method: &str) -> RpcFuture<()> {
+        let method = method.to_string();
+
+        Box::new(self.node_version().and_then(move |version| {
+            if version.has_address_types() {
+                Ok(())
+            } else {
+                Err(Error::UnsupportedByNodeVersion { method, version })
+            }
+        }))
+    }
+
+    /// See `BitcoinCoreClient::call`.
+    pub fn call<R: DeserializeOwned + Debug + Send + 'static>(
+        &self,
+        method: &str,
+        params: &[Value],
+    ) -> RpcFuture<R> {
+        self.send_payload(raw_request(&self.next_id(), method, params))
+    }
+
+    fn get_raw_transaction<R: DeserializeOwned + Debug + Send + 'static>(
+        &self,
+        tx: &TransactionId,
+        verbose: bool,
+    ) -> RpcFuture<R> {
+        self.send(&RpcRequest::new2(
+            JsonRpcVersion::V1,
+            &self.next_id(),
+            "getrawtransaction",
+            tx,
+            verbose,
+        ))
+    }
+}
+
+impl AsyncBitcoinRpcApi for AsyncBitcoinCoreClient {
+    // Order as per: https://bitcoin.org/en/developer-reference#rpcs
+
+    fn add_multisig_address(
+        &self,
+        number_of_required_signatures: u32,
+        participants: Vec<&Address>,
+    ) -> RpcFuture<rpc::MultiSigAddress> {
+        self.send(&RpcRequest::new2(
+            JsonRpcVersion::V1,
+            &self.next_id(),
+            "addmultisigaddress",
+            number_of_required_signatures,
+            participants,
+        ))
+    }
+
+    fn create_raw_transaction(
+        &self,
+        inputs: Vec<&rpc::NewTransactionInput>,
+        output: &rpc::NewTransactionOutput,
+    ) -> RpcFuture<rpc::SerializedRawTransaction> {
+        self.send(&RpcRequest::new2(
+            JsonRpcVersion::V1,
+            &self.next_id(),
+            "createrawtransaction",
+            inputs,
+            output,
+        ))
+    }
+
+    fn decode_rawtransaction(
+        &self,
+        tx: rpc::SerializedRawTransaction,
+    ) -> RpcFuture<rpc::DecodedRawTransaction> {
+        self.send(&RpcRequest::new1(
+            JsonRpcVersion::V1,
+            &self.next_id(),
+            "decoderawtransaction",
+            tx,
+        ))
+    }
+
+    fn decode_script(&self, script: Script) -> RpcFuture<rpc::DecodedScript> {
+        self.send(&RpcRequest::new1(
+            JsonRpcVersion::V1,
+            &self.next_id(),
+            "decodescript",
+            script,
+        ))
+    }
+
+    fn dump_privkey(&self, address: &Address) -> RpcFuture<rpc::PrivateKey> {
+        self.send(&RpcRequest::new1(
+            JsonRpcVersion::V1,
+            &self.next_id(),
+            "dumpprivkey",
+            address,
+        ))
+    }
+
+    fn fund_raw_transaction(
+        &self,
+        tx: &rpc::SerializedRawTransaction,
+        options: &rpc::FundingOptions,
+    ) -> RpcFuture<rpc::FundingResult> {
+        self.send(&RpcRequest::new2(
+            JsonRpcVersion::V1,
+            &self.next_id(),
+            "fundrawtransaction",
+            tx,
+            options,
+        ))
+    }
+
+    fn generate(&self, number_of_blocks: u32) -> RpcFuture<Vec<BlockHash>> {
+        let client = self.clone();
+
+        Box::new(self.node_version().and_then(move |version| {
+            if version.has_generate() {
+                return Either::A(client.send(&RpcRequest::new1(
+                    JsonRpcVersion::V1,
+                    &client.next_id(),
+                    "generate",
+                    number_of_blocks,
+                )));
+            }
+
+            Either::B(client.get_new_address().and_then(move |address| {
+                client.send(&RpcRequest::new2(
+                    JsonRpcVersion::V1,
+                    &client.next_id(),
+                    "generatetoaddress",
+                    number_of_blocks,
+                    address,
+                ))
+            }))
+        }))
+    }
+
+    fn get_address_info(&self, address: &Address) -> RpcFuture<AddressInfoResult> {
+        let client = self.clone();
+        let address = address.clone();
+
+        Box::new(
+            self.require_address_types("getaddressinfo")
+                .and_then(move |()| {
+                    client.send(&RpcRequest::new1(
+                        JsonRpcVersion::V1,
+                        &client.next_id(),
+                        "getaddressinfo",
+                        address,
+                    ))
+                }),
+        )
+    }
+
+    fn get_balance(&self) -> RpcFuture<rpc::Amount> {
+        self.send(&RpcRequest::new0(
+            JsonRpcVersion::V1,
+            &self.next_id(),
+            "getbalance",
+        ))
+    }
+
+    fn get_best_block_hash(&self) -> RpcFuture<BlockHash> {
+        self.send(&RpcRequest::new0(
+            JsonRpcVersion::V1,
+            &self.next_id(),
+            "getbestblockhash",
+        ))
+    }
+
+    fn get_block(&self, header_hash: &BlockHash) -> RpcFuture<rpc::Block<TransactionId>> {
+        self.send(&RpcRequest::new1(
+            JsonRpcVersion::V1,
+            &self.next_id(),
+            "getblock",
+            header_hash,
+        ))
+    }
+
+    fn get_block_verbose(
+        &self,
+        header_hash: &BlockHash,
+    ) -> RpcFuture<rpc::Block<rpc::DecodedRawTransaction>> {
+        self.send(&RpcRequest::new2(
+            JsonRpcVersion::V1,
+            &self.next_id(),
+            "getblock",
+            header_hash,
+            2,
+        ))
+    }
+
+    fn get_blockchain_info(&self) -> RpcFuture<rpc::BlockchainInfo> {
+        self.send(&RpcRequest::new0(
+            JsonRpcVersion::V1,
+            &self.next_id(),
+            "getblockchaininfo",
+        ))
+    }
+
+    fn get_block_count(&self) -> RpcFuture<rpc::BlockHeight> {
+        self.send(&RpcRequest::new0(
+            JsonRpcVersion::V1,
+            &self.next_id(),
+            "getblockcount",
+        ))
+    }
+
+    fn get_block_hash(&self, height: u32) -> RpcFuture<BlockHash> {
+        self.send(&RpcRequest::new1(
+            JsonRpcVersion::V1,
+            &self.next_id(),
+            "getblockhash",
+            height,
+        ))
+    }
+
+    fn get_new_address(&self) -> RpcFuture<Address> {
+        let client = self.clone();
+
+        Box::new(
+            self.require_address_types("getnewaddress")
+                .and_then(move |()| {
+                    client.send(&RpcRequest::new2(
+                        JsonRpcVersion::V1,
+                        &client.next_id(),
+                        "getnewaddress",
+                        "",
+                        "bech32",
+                    ))
+                }),
+        )
+    }
+
+    fn get_raw_transaction_serialized(
+        &self,
+        tx: &TransactionId,
+    ) -> RpcFuture<rpc::SerializedRawTransaction> {
+        self.get_raw_transaction(tx, false)
+    }
+
+    fn get_raw_transaction_verbose(
+        &self,
+        tx: &TransactionId,
+    ) -> RpcFuture<rpc::VerboseRawTransaction> {
+        self.get_raw_transaction(tx, true)
+    }
+
+    fn list_unspent(
+        &self,
+        min_confirmations: rpc::TxOutConfirmations,
+        max_confirmations: Option<u32>,
+        recipients: Option<Vec<Address>>,
+    ) -> RpcFuture<Vec<rpc::UnspentTransactionOutput>> {
+        self.send(&RpcRequest::new3(
+            JsonRpcVersion::V1,
+            &self.next_id(),
+            "listunspent",
+            min_confirmations,
+            max_confirmations,
+            recipients,
+        ))
+    }
+
+    fn send_raw_transaction(
+        &self,
+        tx_data: rpc::SerializedRawTransaction,
+    ) -> RpcFuture<TransactionId> {
+        self.send(&RpcRequest::new1(
+            JsonRpcVersion::V1,
+            &self.next_id(),
+            "sendrawtransaction",
+            tx_data,
+        ))
+    }
+
+    fn send_to_address(&self, address: &Address, amount: rpc::Amount) -> RpcFuture<TransactionId> {
+        self.send(&RpcRequest::new2(
+            JsonRpcVersion::V1,
+            &self.next_id(),
+            "sendtoaddress",
+            address,
+            amount,
+        ))
+    }
+
+    fn sign_raw_transaction_with_key(
+        &self,
+        tx: &rpc::SerializedRawTransaction,
+        private_keys: Option<Vec<&rpc::PrivateKey>>,
+        dependencies: Option<Vec<&rpc::TransactionOutputDetail>>,
+        signature_hash_type: Option<rpc::SigHashType>,
+    ) -> RpcFuture<rpc::SigningResult> {
+        let client = self.clone();
+        let tx = tx.clone();
+        let private_keys = private_keys.map(|keys| keys.into_iter().cloned().collect::<Vec<_>>());
+        let dependencies =
+            dependencies.map(|outputs| outputs.into_iter().cloned().collect::<Vec<_>>());
+
+        Box::new(self.node_version().and_then(move |version| {
+            if version.has_sign_raw_transaction_with_key() {
+                return client.send(&RpcRequest::new4(
+                    JsonRpcVersion::V1,
+                    &client.next_id(),
+                    "signrawtransactionwithkey",
+                    tx,
+                    private_keys,
+                    dependencies,
+                    signature_hash_type,
+                ));
+            }
+
+            // Same result, but the dependencies come before the keys
+            client.send(&RpcRequest::new4(
+                JsonRpcVersion::V1,
+                &client.next_id(),
+                "signrawtransaction",
+                tx,
+                dependencies,
+                private_keys,
+                signature_hash_type,
+            ))
+        }))
+    }
+
+    fn validate_address(&self, address: &Address) -> RpcFuture<rpc::AddressValidationResult> {
+        self.send(&RpcRequest::new1(
+            JsonRpcVersion::V1,
+            &self.next_id(),
+            "validateaddress",
+            address,
+        ))
+    }
+}
+
+impl AsyncBitcoinCoreClient {
+    fn send<R: DeserializeOwned + Debug + Send + 'static, P: Serialize + Debug>(
+        &self,
+        request: &RpcRequest<P>,
+    ) -> RpcFuture<R> {
+        // The request borrows its parameters, serialize it upfront so the future can own it.
+        let request = match serde_json::to_value(request) {
+            Ok(request) => request,
+            Err(e) => return Box::new(future::err(Error::Json(e))),
+        };
+
+        self.send_payload(request)
+    }
+
+    fn send_payload<R: DeserializeOwned + Debug + Send + 'static>(
+        &self,
+        request: Value,
+    ) -> RpcFuture<R> {
+        let client = self.http_client(self.timeouts.read(&request));
+        let url = match self.wallet_url {
+            Some(ref wallet_url) if targets_wallet(&request) => wallet_url.clone(),
+            _ => self.url.clone(),
+        };
+        let auth = self.auth.clone();
+        let id = ids(&request)[0].to_string();
+        let backoff = Backoff::new(self.retry_policy);
+        let method = methods(&request).join(", ");
+
+        Box::new(
+            future::loop_fn(backoff, move |mut backoff| {
+                post::<R>(
+                    client.clone(),
+                    url.clone(),
+                    auth.clone(),
+                    method.clone(),
+                    request.clone(),
+                )
+                .then(move |outcome| {
+                    match backoff.next_delay(&outcome, warming_up) {
+                        Some(delay) => Either::A(
+                            Delay::new(Instant::now() + delay)
+                                .then(move |_| Ok(Loop::Continue(backoff))),
+                        ),
+                        None => Either::B(future::result(outcome.map(Loop::Break))),
+                    }
+                })
+            })
+            .and_then(move |response| verify_id(&id, response))
+            .and_then(|response| response.into_result().map_err(Error::Rpc)),
+        )
+    }
+
+    /// See `BitcoinCoreClient::http_client`.
+    fn http_client(&self, read_timeout: Option<Duration>) -> AsyncHTTPClient {
+        let mut clients = self.clients.lock().unwrap();
+
+        clients
+            .entry(read_timeout)
+            .or_insert_with(|| {
+                let mut builder = AsyncHTTPClient::builder();
+
+                if let Some(connect_timeout) = self.timeouts.connect() {
+                    builder = builder.connect_timeout(connect_timeout);
+                }
+                if let Some(read_timeout) = read_timeout {
+                    builder = builder.timeout(read_timeout);
+                }
+
+                builder.build().expect("unable to create HTTP client")
+            })
+            .clone()
+    }
+}
+
+fn warming_up<R>(response: &RpcResponse<R>) -> bool {
+    match response.error {
+        Some(ref rpc_error) => is_warming_up(rpc_error),
+        None => false,
+    }
+}
+
+fn post<R: DeserializeOwned>(
+    client: AsyncHTTPClient,
+    url: String,
+    auth: Arc<Authenticator>,
+    method: String,
+    request: Value,
+) -> impl Future<Item = RpcResponse<R>, Error = Error> {
+    trace!(">>> {}", request);
+
+    post_authenticated(&client, &url, &auth, &request)
+        .and_then(move |response| {
+            if response.status() == StatusCode::UNAUTHORIZED && auth.refresh() {
+                Either::A(post_authenticated(&client, &url, &auth, &request))
+            } else {
+                Either::B(future::ok(response))
+            }
+        })
+        .map_err(Error::from)
+        .and_then(|response| {
+            if is_http_error(response.status()) {
+                Either::A(future::err(Error::HttpStatus(response.status())))
+            } else {
+                Either::B(response.into_body().concat2().map_err(Error::from))
+            }
+        })
+        .and_then(move |body| {
+            trace!("<<< {}", String::from_utf8_lossy(&body));
+            deserialize_response(&method, &body)
+        })
+}
+
+fn post_authenticated(
+    client: &AsyncHTTPClient,
+    url: &str,
+    auth: &Authenticator,
+    request: &Value,
+) -> impl Future<Item = Response, Error = reqwest::Error> {
+    client
+        .post(url)
+        .header(AUTHORIZATION, auth.header())
+        .header(CONTENT_TYPE, "application/json")
+        .json(request)
+        .send()
+}
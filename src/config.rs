@@ -0,0 +1,402 @@
+use auth::Auth;
+use bitcoin::network::constants::Network;
+use std::{
+    env,
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Options that only apply to the network of the section they are defined in.
+///
+/// Outside of a section, bitcoind only applies them to mainnet.
+const NETWORK_ONLY_OPTIONS: &[&str] = &["rpcport", "rpcbind"];
+
+/// Where and how to reach the RPC interface of a node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcConfig {
+    pub url: String,
+    pub auth: Auth,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    InvalidLine { line: usize, content: String },
+    InvalidValue { option: String, value: String },
+}
+
+impl RpcConfig {
+    /// Reads `bitcoin.conf` from `datadir`, falling back to the defaults of bitcoind if it
+    /// does not exist.
+    pub fn from_datadir<P: AsRef<Path>>(datadir: P, network: Network) -> Result<Self, ConfigError> {
+        let datadir = datadir.as_ref();
+        let conf_file = datadir.join("bitcoin.conf");
+
+        if conf_file.exists() {
+            Self::from_config_file(conf_file, datadir, network)
+        } else {
+            Self::parse("", datadir, network)
+        }
+    }
+
+    pub fn from_config_file<P: AsRef<Path>, D: AsRef<Path>>(
+        conf_file: P,
+        datadir: D,
+        network: Network,
+    ) -> Result<Self, ConfigError> {
+        let content = fs::read_to_string(conf_file)?;
+
+        Self::parse(&content, datadir.as_ref(), network)
+    }
+
+    /// Resolves the RPC settings for `network` from the content of a `bitcoin.conf` file.
+    pub fn parse(content: &str, datadir: &Path, network: Network) -> Result<Self, ConfigError> {
+        let conf = ConfFile::parse(content)?;
+        let get = |option: &str| conf.get(network, option);
+
+        let datadir = get("datadir").map_or_else(|| datadir.to_path_buf(), PathBuf::from);
+        let network_datadir = network_datadir(&datadir, network);
+
+        let (host, bind_port) = match get("rpcconnect").or_else(|| get("rpcbind")) {
+            Some(host) => split_host_port(host)?,
+            None => ("127.0.0.1".to_string(), None),
+        };
+        let port = match get("rpcport") {
+            Some(port) => parse_port("rpcport", port)?,
+            None => bind_port.unwrap_or_else(|| default_rpc_port(network)),
+        };
+
+        let auth = match get("rpcpassword") {
+            Some(password) => Auth::user_pass(get("rpcuser").unwrap_or(""), password),
+            None => Auth::CookieFile(get("rpccookiefile").map_or_else(
+                || network_datadir.join(".cookie"),
+                |cookie_file| network_datadir.join(cookie_file),
+            )),
+        };
+
+        Ok(RpcConfig {
+            url: format!("http://{}:{}", host, port),
+            auth,
+        })
+    }
+}
+
+pub fn default_rpc_port(network: Network) -> u16 {
+    match network {
+        Network::Bitcoin => 8332,
+        Network::Testnet => 18332,
+        Network::Regtest => 18443,
+    }
+}
+
+/// The platform specific directory bitcoind uses if no `-datadir` is given.
+pub fn default_datadir() -> Option<PathBuf> {
+    if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(|appdata| PathBuf::from(appdata).join("Bitcoin"))
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join("Library/Application Support/Bitcoin"))
+    } else {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join(".bitcoin"))
+    }
+}
+
+fn network_datadir(datadir: &Path, network: Network) -> PathBuf {
+    match network {
+        Network::Bitcoin => datadir.to_path_buf(),
+        Network::Testnet => datadir.join("testnet3"),
+        Network::Regtest => datadir.join("regtest"),
+    }
+}
+
+fn section_name(network: Network) -> &'static str {
+    match network {
+        Network::Bitcoin => "main",
+        Network::Testnet => "test",
+        Network::Regtest => "regtest",
+    }
+}
+
+fn split_host_port(value: &str) -> Result<(String, Option<u16>), ConfigError> {
+    let (host, port) = if value.starts_with('[') {
+        // [ipv6]:port
+        match value.find(']') {
+            Some(end) => (&value[..=end], value[end + 1..].trim_start_matches(':')),
+            None => return Err(invalid_value("rpcbind", value)),
+        }
+    } else if value.matches(':').count() == 1 {
+        let mut parts = value.splitn(2, ':');
+        (parts.next().unwrap(), parts.next().unwrap())
+    } else {
+        (value, "")
+    };
+
+    let host = match host {
+        "" | "0.0.0.0" => "127.0.0.1",
+        "::" | "[::]" => "[::1]",
+        host => host,
+    };
+    let port = if port.is_empty() {
+        None
+    } else {
+        Some(parse_port("rpcbind", port)?)
+    };
+
+    Ok((host.to_string(), port))
+}
+
+fn parse_port(option: &str, value: &str) -> Result<u16, ConfigError> {
+    value.parse().map_err(|_| invalid_value(option, value))
+}
+
+fn invalid_value(option: &str, value: &str) -> ConfigError {
+    ConfigError::InvalidValue {
+        option: option.to_string(),
+        value: value.to_string(),
+    }
+}
+
+struct ConfFile {
+    entries: Vec<Entry>,
+}
+
+struct Entry {
+    section: Option<String>,
+    key: String,
+    value: String,
+}
+
+impl ConfFile {
+    fn parse(content: &str) -> Result<Self, ConfigError> {
+        let mut section = None;
+        let mut entries = Vec::new();
+
+        for (index, line) in content.lines().enumerate() {
+            let line = match line.find('#') {
+                Some(comment) => &line[..comment],
+                None => line,
+            }
+            .trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                section = Some(line[1..line.len() - 1].trim().to_string());
+                continue;
+            }
+
+            let mut parts = line.splitn(2, '=');
+            let (key, value) = match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) => (key.trim(), value.trim()),
+                _ => {
+                    return Err(ConfigError::InvalidLine {
+                        line: index + 1,
+                        content: line.to_string(),
+                    });
+                }
+            };
+
+            // `regtest.rpcport=1234` is equivalent to `rpcport=1234` in the `[regtest]` section
+            let (section, key) = match key.find('.') {
+                Some(dot) => (Some(key[..dot].to_string()), &key[dot + 1..]),
+                None => (section.clone(), key),
+            };
+
+            entries.push(Entry {
+                section,
+                key: key.to_string(),
+                value: value.to_string(),
+            });
+        }
+
+        Ok(ConfFile { entries })
+    }
+
+    /// Values of the network section win over top-level ones. As in bitcoind, the first
+    /// occurrence of an option is used.
+    fn get(&self, network: Network, key: &str) -> Option<&str> {
+        let section = section_name(network);
+        let top_level_applies = network == Network::Bitcoin || !NETWORK_ONLY_OPTIONS.contains(&key);
+
+        let find = |wanted: Option<&str>| {
+            self.entries
+                .iter()
+                .find(|entry| entry.key == key && entry.section.as_deref() == wanted)
+                .map(|entry| entry.value.as_str())
+        };
+
+        find(Some(section)).or_else(|| if top_level_applies { find(None) } else { None })
+    }
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(e: io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::Io(ref e) => write!(f, "unable to read config: {}", e),
+            ConfigError::InvalidLine { line, ref content } => {
+                write!(f, "invalid line {} in config: {}", line, content)
+            }
+            ConfigError::InvalidValue {
+                ref option,
+                ref value,
+            } => write!(f, "invalid value for {}: {}", option, value),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str, network: Network) -> RpcConfig {
+        RpcConfig::parse(content, Path::new("/home/satoshi/.bitcoin"), network).unwrap()
+    }
+
+    #[test]
+    fn defaults_to_cookie_auth_on_localhost() {
+        assert_eq!(
+            parse("", Network::Bitcoin),
+            RpcConfig {
+                url: String::from("http://127.0.0.1:8332"),
+                auth: Auth::cookie_file("/home/satoshi/.bitcoin/.cookie"),
+            }
+        );
+        assert_eq!(
+            parse("", Network::Testnet),
+            RpcConfig {
+                url: String::from("http://127.0.0.1:18332"),
+                auth: Auth::cookie_file("/home/satoshi/.bitcoin/testnet3/.cookie"),
+            }
+        );
+        assert_eq!(
+            parse("", Network::Regtest),
+            RpcConfig {
+                url: String::from("http://127.0.0.1:18443"),
+                auth: Auth::cookie_file("/home/satoshi/.bitcoin/regtest/.cookie"),
+            }
+        );
+    }
+
+    #[test]
+    fn reads_user_pass_and_port() {
+        let conf = r#"
+# Comment
+server=1
+rpcuser=bitcoin # trailing comment
+rpcpassword = secret
+rpcport=1234
+"#;
+
+        assert_eq!(
+            parse(conf, Network::Bitcoin),
+            RpcConfig {
+                url: String::from("http://127.0.0.1:1234"),
+                auth: Auth::user_pass("bitcoin", "secret"),
+            }
+        );
+    }
+
+    #[test]
+    fn network_section_overrides_top_level() {
+        let conf = r#"
+rpcuser=bitcoin
+rpcpassword=secret
+rpcport=1234
+
+[regtest]
+rpcpassword=regtest-secret
+rpcbind=192.168.1.2:5678
+
+[test]
+rpcport=9999
+"#;
+
+        assert_eq!(
+            parse(conf, Network::Regtest),
+            RpcConfig {
+                url: String::from("http://192.168.1.2:5678"),
+                auth: Auth::user_pass("bitcoin", "regtest-secret"),
+            }
+        );
+        assert_eq!(
+            parse(conf, Network::Testnet),
+            RpcConfig {
+                url: String::from("http://127.0.0.1:9999"),
+                auth: Auth::user_pass("bitcoin", "secret"),
+            }
+        );
+    }
+
+    #[test]
+    fn top_level_rpcport_only_applies_to_mainnet() {
+        let conf = "rpcport=1234\nrpcbind=10.0.0.1";
+
+        assert_eq!(parse(conf, Network::Bitcoin).url, "http://10.0.0.1:1234");
+        assert_eq!(parse(conf, Network::Regtest).url, "http://127.0.0.1:18443");
+    }
+
+    #[test]
+    fn supports_network_prefixed_options() {
+        let conf = "regtest.rpcport=1234\ntest.rpcport=5678";
+
+        assert_eq!(parse(conf, Network::Regtest).url, "http://127.0.0.1:1234");
+        assert_eq!(parse(conf, Network::Testnet).url, "http://127.0.0.1:5678");
+    }
+
+    #[test]
+    fn resolves_relative_cookie_file_and_datadir() {
+        assert_eq!(
+            parse("rpccookiefile=auth/cookie", Network::Regtest).auth,
+            Auth::cookie_file("/home/satoshi/.bitcoin/regtest/auth/cookie")
+        );
+        assert_eq!(
+            parse("datadir=/data", Network::Testnet).auth,
+            Auth::cookie_file("/data/testnet3/.cookie")
+        );
+        assert_eq!(
+            parse("rpccookiefile=/run/bitcoind/cookie", Network::Bitcoin).auth,
+            Auth::cookie_file("/run/bitcoind/cookie")
+        );
+    }
+
+    #[test]
+    fn maps_wildcard_and_ipv6_bind_addresses() {
+        assert_eq!(
+            parse("rpcbind=0.0.0.0", Network::Bitcoin).url,
+            "http://127.0.0.1:8332"
+        );
+        assert_eq!(
+            parse("rpcbind=[::1]:1234", Network::Bitcoin).url,
+            "http://[::1]:1234"
+        );
+        assert_eq!(
+            parse("rpcconnect=10.0.0.2\nrpcbind=10.0.0.1", Network::Bitcoin).url,
+            "http://10.0.0.2:8332"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_config() {
+        let datadir = Path::new("/home/satoshi/.bitcoin");
+
+        match RpcConfig::parse("rpcuser", datadir, Network::Bitcoin) {
+            Err(ConfigError::InvalidLine { line: 1, .. }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match RpcConfig::parse("rpcport=abc", datadir, Network::Bitcoin) {
+            Err(ConfigError::InvalidValue { .. }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
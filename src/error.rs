@@ -0,0 +1,159 @@
+use error_code::RpcErrorCode;
+use jsonrpc_client::RpcError;
+use reject_reason::RejectReason;
+use reqwest::{self, StatusCode};
+use serde_json;
+use std::{error, fmt, io};
+use version::NodeVersion;
+
+/// Everything that can go wrong when calling an RPC.
+#[derive(Debug)]
+pub enum Error {
+    Transport(reqwest::Error),
+    /// The connection was closed before the node answered, as injected by
+    /// `Fault::ConnectionReset`. Genuine resets are reported as `Transport` errors and handled
+    /// the same way.
+    #[cfg(feature = "test-support")]
+    ConnectionReset,
+    /// The node did not accept the connection or answer the request in time.
+    Timeout(reqwest::Error),
+    /// The node answered with a status code that comes without a JSON body, e.g. 401 if the
+    /// credentials are wrong.
+    HttpStatus(StatusCode),
+    Json(serde_json::Error),
+    /// The response to `method` does not have the expected shape, e.g. because the node runs a
+    /// version of Bitcoin Core this crate does not support.
+    Deserialization {
+        method: String,
+        /// The path to the offending field, e.g. `result.vin[0].txid`.
+        path: String,
+        source: serde_json::Error,
+        /// The beginning of the response body.
+        response: String,
+    },
+    /// The node answered with a response that does not belong to the sent request.
+    IdMismatch {
+        expected: String,
+        actual: String,
+    },
+    /// None of the nodes of a `FailoverClient` passed the health check.
+    NoHealthyNode,
+    /// The node runs a version of Bitcoin Core that does not provide `method` in a form this
+    /// crate can use.
+    UnsupportedByNodeVersion {
+        method: String,
+        version: NodeVersion,
+    },
+    /// A client replaying a fixture was asked to send a request that was not recorded.
+    UnexpectedRequest {
+        request: String,
+    },
+    /// Reading or writing a fixture file failed.
+    Io(io::Error),
+    /// The node processed the request but reported an error.
+    Rpc(RpcError),
+}
+
+impl Error {
+    /// The error reported by the node, if the request got that far.
+    pub fn rpc_error(&self) -> Option<&RpcError> {
+        match *self {
+            Error::Rpc(ref rpc_error) => Some(rpc_error),
+            _ => None,
+        }
+    }
+
+    /// The meaning of the error reported by the node, if the request got that far.
+    pub fn rpc_error_code(&self) -> Option<RpcErrorCode> {
+        self.rpc_error()
+            .map(|rpc_error| RpcErrorCode::from(rpc_error.code))
+    }
+
+    /// Why the node rejected a transaction passed to `send_raw_transaction`.
+    pub fn reject_reason(&self) -> Option<RejectReason> {
+        self.rpc_error().and_then(RejectReason::from_rpc_error)
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            Error::Timeout(e)
+        } else {
+            Error::Transport(e)
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<RpcError> for Error {
+    fn from(e: RpcError) -> Self {
+        Error::Rpc(e)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Transport(ref e) => write!(f, "transport error: {}", e),
+            Error::Timeout(ref e) => write!(f, "timed out: {}", e),
+            #[cfg(feature = "test-support")]
+            Error::ConnectionReset => write!(f, "connection reset before the node answered"),
+            Error::HttpStatus(status) => write!(f, "node answered with HTTP status {}", status),
+            Error::Json(ref e) => write!(f, "invalid JSON: {}", e),
+            Error::Deserialization {
+                ref method,
+                ref path,
+                ref source,
+                ref response,
+            } => write!(
+                f,
+                "failed to deserialize response to {} at {}: {}, response: {}",
+                method, path, source, response
+            ),
+            Error::IdMismatch {
+                ref expected,
+                ref actual,
+            } => write!(
+                f,
+                "expected response to request {} but got response to request {}",
+                expected, actual
+            ),
+            Error::NoHealthyNode => write!(f, "no healthy node available"),
+            Error::UnsupportedByNodeVersion {
+                ref method,
+                version,
+            } => write!(f, "{} is not supported by Bitcoin Core {}", method, version),
+            Error::UnexpectedRequest { ref request } => {
+                write!(f, "no recorded response to request {}", request)
+            }
+            Error::Io(ref e) => write!(f, "I/O error: {}", e),
+            Error::Rpc(ref e) => write!(f, "RPC error {}: {}", e.code, e.message),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Transport(ref e) => Some(e),
+            Error::Timeout(ref e) => Some(e),
+            Error::Json(ref e) => Some(e),
+            Error::Deserialization { ref source, .. } => Some(source),
+            Error::Io(ref e) => Some(e),
+            #[cfg(feature = "test-support")]
+            Error::ConnectionReset => None,
+            Error::HttpStatus(_)
+            | Error::IdMismatch { .. }
+            | Error::NoHealthyNode
+            | Error::UnsupportedByNodeVersion { .. }
+            | Error::UnexpectedRequest { .. }
+            | Error::Rpc(_) => None,
+        }
+    }
+}
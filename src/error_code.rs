@@ -0,0 +1,179 @@
+/// The meaning of an `RpcError` code, as defined by `RPCErrorCode` in Bitcoin Core's
+/// `rpc/protocol.h`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RpcErrorCode {
+    // Standard JSON-RPC 2.0 errors
+    InvalidRequest,
+    MethodNotFound,
+    InvalidParams,
+    InternalError,
+    ParseError,
+
+    // General application defined errors
+    MiscError,
+    /// Only returned by nodes before 0.17.
+    ForbiddenBySafeMode,
+    TypeError,
+    InvalidAddressOrKey,
+    OutOfMemory,
+    InvalidParameter,
+    DatabaseError,
+    DeserializationError,
+    VerifyError,
+    VerifyRejected,
+    VerifyAlreadyInChain,
+    InWarmup,
+    MethodDeprecated,
+
+    // P2P client errors
+    ClientNotConnected,
+    ClientInInitialDownload,
+    ClientNodeAlreadyAdded,
+    ClientNodeNotAdded,
+    ClientNodeNotConnected,
+    ClientInvalidIpOrSubnet,
+    ClientP2PDisabled,
+
+    // Chain errors
+    ClientMempoolDisabled,
+    ClientNodeCapacityReached,
+
+    // Wallet errors
+    WalletError,
+    WalletInsufficientFunds,
+    WalletInvalidLabelName,
+    WalletKeypoolRanOut,
+    WalletUnlockNeeded,
+    WalletPassphraseIncorrect,
+    WalletWrongEncState,
+    WalletEncryptionFailed,
+    WalletAlreadyUnlocked,
+    WalletNotFound,
+    WalletNotSpecified,
+    WalletAlreadyLoaded,
+    /// Only returned by nodes since 22.0.
+    WalletAlreadyExists,
+
+    /// A code this crate does not know about.
+    Unknown(i32),
+}
+
+impl RpcErrorCode {
+    pub fn code(self) -> i32 {
+        use self::RpcErrorCode::*;
+
+        match self {
+            InvalidRequest => -32600,
+            MethodNotFound => -32601,
+            InvalidParams => -32602,
+            InternalError => -32603,
+            ParseError => -32700,
+            MiscError => -1,
+            ForbiddenBySafeMode => -2,
+            TypeError => -3,
+            InvalidAddressOrKey => -5,
+            OutOfMemory => -7,
+            InvalidParameter => -8,
+            DatabaseError => -20,
+            DeserializationError => -22,
+            VerifyError => -25,
+            VerifyRejected => -26,
+            VerifyAlreadyInChain => -27,
+            InWarmup => -28,
+            MethodDeprecated => -32,
+            ClientNotConnected => -9,
+            ClientInInitialDownload => -10,
+            ClientNodeAlreadyAdded => -23,
+            ClientNodeNotAdded => -24,
+            ClientNodeNotConnected => -29,
+            ClientInvalidIpOrSubnet => -30,
+            ClientP2PDisabled => -31,
+            ClientMempoolDisabled => -33,
+            ClientNodeCapacityReached => -34,
+            WalletError => -4,
+            WalletInsufficientFunds => -6,
+            WalletInvalidLabelName => -11,
+            WalletKeypoolRanOut => -12,
+            WalletUnlockNeeded => -13,
+            WalletPassphraseIncorrect => -14,
+            WalletWrongEncState => -15,
+            WalletEncryptionFailed => -16,
+            WalletAlreadyUnlocked => -17,
+            WalletNotFound => -18,
+            WalletNotSpecified => -19,
+            WalletAlreadyLoaded => -35,
+            WalletAlreadyExists => -36,
+            Unknown(code) => code,
+        }
+    }
+}
+
+impl From<i32> for RpcErrorCode {
+    fn from(code: i32) -> Self {
+        use self::RpcErrorCode::*;
+
+        match code {
+            -32600 => InvalidRequest,
+            -32601 => MethodNotFound,
+            -32602 => InvalidParams,
+            -32603 => InternalError,
+            -32700 => ParseError,
+            -1 => MiscError,
+            -2 => ForbiddenBySafeMode,
+            -3 => TypeError,
+            -5 => InvalidAddressOrKey,
+            -7 => OutOfMemory,
+            -8 => InvalidParameter,
+            -20 => DatabaseError,
+            -22 => DeserializationError,
+            -25 => VerifyError,
+            -26 => VerifyRejected,
+            -27 => VerifyAlreadyInChain,
+            -28 => InWarmup,
+            -32 => MethodDeprecated,
+            -9 => ClientNotConnected,
+            -10 => ClientInInitialDownload,
+            -23 => ClientNodeAlreadyAdded,
+            -24 => ClientNodeNotAdded,
+            -29 => ClientNodeNotConnected,
+            -30 => ClientInvalidIpOrSubnet,
+            -31 => ClientP2PDisabled,
+            -33 => ClientMempoolDisabled,
+            -34 => ClientNodeCapacityReached,
+            -4 => WalletError,
+            -6 => WalletInsufficientFunds,
+            -11 => WalletInvalidLabelName,
+            -12 => WalletKeypoolRanOut,
+            -13 => WalletUnlockNeeded,
+            -14 => WalletPassphraseIncorrect,
+            -15 => WalletWrongEncState,
+            -16 => WalletEncryptionFailed,
+            -17 => WalletAlreadyUnlocked,
+            -18 => WalletNotFound,
+            -19 => WalletNotSpecified,
+            -35 => WalletAlreadyLoaded,
+            -36 => WalletAlreadyExists,
+            code => Unknown(code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_codes_in_both_directions() {
+        for code in (-40..0).chain(-32700..-32599) {
+            assert_eq!(RpcErrorCode::from(code).code(), code);
+        }
+
+        assert_eq!(
+            RpcErrorCode::from(-6),
+            RpcErrorCode::WalletInsufficientFunds
+        );
+        assert_eq!(RpcErrorCode::from(-26), RpcErrorCode::VerifyRejected);
+        assert_eq!(RpcErrorCode::from(-36), RpcErrorCode::WalletAlreadyExists);
+        assert_eq!(RpcErrorCode::from(-37), RpcErrorCode::Unknown(-37));
+    }
+}
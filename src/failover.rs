@@ -0,0 +1,625 @@
+use bitcoin::{Address, Network, Script};
+use bitcoincore::BitcoinCoreClient;
+use error::Error;
+use reqwest::StatusCode;
+use retry::{is_connection_refused, is_idempotent_method};
+use rpc;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use types::address::AddressInfoResult;
+use wallet::is_wallet_method;
+use BitcoinRpcApi;
+use BlockHash;
+use TransactionId;
+
+/// Sends every RPC to the first healthy one of several redundant nodes.
+///
+/// A node is healthy if it answers `getblockchaininfo`, is on the expected chain and is at most
+/// `max_block_lag` blocks behind the best node. The expected chain is either configured through
+/// `with_network` or the one most nodes are on.
+///
+/// If a node fails with a transport error, a timeout or an HTTP status such as 503, it is
+/// considered unhealthy until the next health check and the RPC is sent to the next healthy
+/// node. Non-idempotent RPCs such as `sendtoaddress` are only sent to another node if the first
+/// one certainly did not process them, e.g. because it refused the connection.
+///
+/// Wallet RPCs such as `getbalance` or `sendtoaddress` are only sent to the first node, since
+/// another node would silently answer them from a different wallet. They don't fail over and
+/// fail with `Error::NoHealthyNode` while the first node is unhealthy.
+///
+/// The nodes retry on their own according to their `RetryPolicy`, which should be short for a
+/// timely failover.
+pub struct FailoverClient {
+    nodes: Vec<BitcoinCoreClient>,
+    network: Option<Network>,
+    max_block_lag: u64,
+    health_check_interval: Duration,
+    health: Mutex<Health>,
+}
+
+struct Health {
+    healthy: Vec<bool>,
+    checked_at: Option<Instant>,
+}
+
+/// The chain and height reported by a node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Tip {
+    network: Network,
+    blocks: u64,
+}
+
+impl FailoverClient {
+    /// The nodes are tried in the given order.
+    pub fn new(nodes: Vec<BitcoinCoreClient>) -> Self {
+        let healthy = vec![false; nodes.len()];
+
+        FailoverClient {
+            nodes,
+            network: None,
+            max_block_lag: 2,
+            health_check_interval: Duration::from_secs(30),
+            health: Mutex::new(Health {
+                healthy,
+                checked_at: None,
+            }),
+        }
+    }
+
+    pub fn with_network(self, network: Network) -> Self {
+        FailoverClient {
+            network: Some(network),
+            ..self
+        }
+    }
+    pub fn with_max_block_lag(self, max_block_lag: u64) -> Self {
+        FailoverClient {
+            max_block_lag,
+            ..self
+        }
+    }
+    pub fn with_health_check_interval(self, health_check_interval: Duration) -> Self {
+        FailoverClient {
+            health_check_interval,
+            ..self
+        }
+    }
+
+    /// Queries all nodes and updates which of them are used.
+    pub fn check_health(&self) {
+        let tips = self
+            .nodes
+            .iter()
+            .map(|node| match node.get_blockchain_info() {
+                Ok(info) => Some(Tip {
+                    network: info.chain,
+                    blocks: info.blocks,
+                }),
+                Err(e) => {
+                    warn!("Health check failed: {}", e);
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let healthy = healthy_nodes(&tips, self.network, self.max_block_lag);
+        let mut health = self.health.lock().unwrap();
+
+        health.healthy = healthy;
+        health.checked_at = Some(Instant::now());
+    }
+
+    /// Whether the node at `index` is currently used, as of the last health check.
+    pub fn is_healthy(&self, index: usize) -> bool {
+        let health = self.health.lock().unwrap();
+
+        health.healthy.get(index).cloned().unwrap_or(false)
+    }
+
+    fn healthy_indices(&self) -> Vec<usize> {
+        let needs_check = {
+            let health = self.health.lock().unwrap();
+
+            match health.checked_at {
+                Some(checked_at) => {
+                    checked_at.elapsed() >= self.health_check_interval
+                        || !health.healthy.contains(&true)
+                }
+                None => true,
+            }
+        };
+
+        if needs_check {
+            self.check_health();
+        }
+
+        let health = self.health.lock().unwrap();
+
+        (0..self.nodes.len())
+            .filter(|&index| health.healthy[index])
+            .collect()
+    }
+
+    fn mark_unhealthy(&self, index: usize) {
+        let mut health = self.health.lock().unwrap();
+
+        health.healthy[index] = false;
+    }
+
+    fn route<R, F>(&self, method: &str, call: F) -> Result<R, Error>
+    where
+        F: Fn(&BitcoinCoreClient) -> Result<R, Error>,
+    {
+        if is_wallet_method(method) {
+            return self.route_to_first(method, call);
+        }
+
+        let mut last_error = None;
+
+        for index in self.healthy_indices() {
+            match call(&self.nodes[index]) {
+                Err(e) => {
+                    if !is_failover_error(&e, method) {
+                        return Err(e);
+                    }
+
+                    warn!("Node {} failed, failing over: {}", index, e);
+                    self.mark_unhealthy(index);
+                    last_error = Some(e);
+                }
+                outcome => return outcome,
+            }
+        }
+
+        Err(last_error.unwrap_or(Error::NoHealthyNode))
+    }
+
+    /// Sends the RPC to the first node if it is healthy, never to another one.
+    fn route_to_first<R, F>(&self, method: &str, call: F) -> Result<R, Error>
+    where
+        F: Fn(&BitcoinCoreClient) -> Result<R, Error>,
+    {
+        if !self.healthy_indices().contains(&0) {
+            return Err(Error::NoHealthyNode);
+        }
+
+        call(&self.nodes[0]).inspect_err(|e| {
+            if is_failover_error(e, method) {
+                self.mark_unhealthy(0);
+            }
+        })
+    }
+}
+
+/// Whether to send the RPC to the next node after `error`.
+///
+/// A node that refused the connection, is overloaded or rejects the credentials has not
+/// processed the request, so any RPC can be sent to the next one.
+fn is_failover_error(error: &Error, method: &str) -> bool {
+    match *error {
+        Error::Transport(ref e) if is_connection_refused(e) => true,
+        Error::HttpStatus(StatusCode::SERVICE_UNAVAILABLE)
+        | Error::HttpStatus(StatusCode::UNAUTHORIZED)
+        | Error::HttpStatus(StatusCode::FORBIDDEN) => true,
+        Error::Transport(_) | Error::Timeout(_) => is_idempotent_method(method),
+        #[cfg(feature = "test-support")]
+        Error::ConnectionReset => is_idempotent_method(method),
+        _ => false,
+    }
+}
+
+/// A node is healthy if it is on the expected chain and not too far behind the best node on it.
+///
+/// Without an expected network, the network of most nodes is expected. On a tie, the network of
+/// the node that comes first wins.
+fn healthy_nodes(tips: &[Option<Tip>], network: Option<Network>, max_block_lag: u64) -> Vec<bool> {
+    let network = network.or_else(|| {
+        let networks = tips.iter().flatten().map(|tip| tip.network);
+        let count = |network| networks.clone().filter(|n| *n == network).count();
+
+        networks.clone().fold(None, |best, network| match best {
+            Some(best) if count(best) >= count(network) => Some(best),
+            _ => Some(network),
+        })
+    });
+    let best_height = tips
+        .iter()
+        .flatten()
+        .filter(|tip| Some(tip.network) == network)
+        .map(|tip| tip.blocks)
+        .max();
+
+    tips.iter()
+        .map(|tip| match (tip, best_height) {
+            (Some(tip), Some(best_height)) => {
+                Some(tip.network) == network && best_height - tip.blocks <= max_block_lag
+            }
+            _ => false,
+        })
+        .collect()
+}
+
+impl BitcoinRpcApi for FailoverClient {
+    fn add_multisig_address(
+        &self,
+        number_of_required_signatures: u32,
+        participants: Vec<&Address>,
+    ) -> Result<rpc::MultiSigAddress, Error> {
+        self.route("addmultisigaddress", |node| {
+            node.add_multisig_address(number_of_required_signatures, participants.clone())
+        })
+    }
+
+    fn create_raw_transaction(
+        &self,
+        inputs: Vec<&rpc::NewTransactionInput>,
+        output: &rpc::NewTransactionOutput,
+    ) -> Result<rpc::SerializedRawTransaction, Error> {
+        self.route("createrawtransaction", |node| {
+            node.create_raw_transaction(inputs.clone(), output)
+        })
+    }
+
+    fn decode_rawtransaction(
+        &self,
+        tx: rpc::SerializedRawTransaction,
+    ) -> Result<rpc::DecodedRawTransaction, Error> {
+        self.route("decoderawtransaction", |node| {
+            node.decode_rawtransaction(tx.clone())
+        })
+    }
+
+    fn decode_script(&self, script: Script) -> Result<rpc::DecodedScript, Error> {
+        self.route("decodescript", |node| node.decode_script(script.clone()))
+    }
+
+    fn dump_privkey(&self, address: &Address) -> Result<rpc::PrivateKey, Error> {
+        self.route("dumpprivkey", |node| node.dump_privkey(address))
+    }
+
+    fn fund_raw_transaction(
+        &self,
+        tx: &rpc::SerializedRawTransaction,
+        options: &rpc::FundingOptions,
+    ) -> Result<rpc::FundingResult, Error> {
+        self.route("fundrawtransaction", |node| {
+            node.fund_raw_transaction(tx, options)
+        })
+    }
+
+    fn generate(&self, number_of_blocks: u32) -> Result<Vec<BlockHash>, Error> {
+        self.route("generate", |node| node.generate(number_of_blocks))
+    }
+
+    fn get_address_info(&self, address: &Address) -> Result<AddressInfoResult, Error> {
+        self.route("getaddressinfo", |node| node.get_address_info(address))
+    }
+
+    fn get_balance(&self) -> Result<rpc::Amount, Error> {
+        self.route("getbalance", BitcoinCoreClient::get_balance)
+    }
+
+    fn get_best_block_hash(&self) -> Result<BlockHash, Error> {
+        self.route("getbestblockhash", BitcoinCoreClient::get_best_block_hash)
+    }
+
+    fn get_block(&self, header_hash: &BlockHash) -> Result<rpc::Block<TransactionId>, Error> {
+        self.route("getblock", |node| node.get_block(header_hash))
+    }
+
+    fn get_block_verbose(
+        &self,
+        header_hash: &BlockHash,
+    ) -> Result<rpc::Block<rpc::DecodedRawTransaction>, Error> {
+        self.route("getblock", |node| node.get_block_verbose(header_hash))
+    }
+
+    fn get_blockchain_info(&self) -> Result<rpc::BlockchainInfo, Error> {
+        self.route("getblockchaininfo", BitcoinCoreClient::get_blockchain_info)
+    }
+
+    fn get_block_count(&self) -> Result<rpc::BlockHeight, Error> {
+        self.route("getblockcount", BitcoinCoreClient::get_block_count)
+    }
+
+    fn get_block_hash(&self, height: u32) -> Result<BlockHash, Error> {
+        self.route("getblockhash", |node| node.get_block_hash(height))
+    }
+
+    fn get_new_address(&self) -> Result<Address, Error> {
+        self.route("getnewaddress", BitcoinCoreClient::get_new_address)
+    }
+
+    fn get_raw_transaction_serialized(
+        &self,
+        tx: &TransactionId,
+    ) -> Result<rpc::SerializedRawTransaction, Error> {
+        self.route("getrawtransaction", |node| {
+            node.get_raw_transaction_serialized(tx)
+        })
+    }
+
+    fn get_raw_transaction_verbose(
+        &self,
+        tx: &TransactionId,
+    ) -> Result<rpc::VerboseRawTransaction, Error> {
+        self.route("getrawtransaction", |node| {
+            node.get_raw_transaction_verbose(tx)
+        })
+    }
+
+    fn list_unspent(
+        &self,
+        min_confirmations: rpc::TxOutConfirmations,
+        max_confirmations: Option<u32>,
+        recipients: Option<Vec<Address>>,
+    ) -> Result<Vec<rpc::UnspentTransactionOutput>, Error> {
+        self.route("listunspent", |node| {
+            node.list_unspent(min_confirmations, max_confirmations, recipients.clone())
+        })
+    }
+
+    fn send_raw_transaction(
+        &self,
+        tx_data: rpc::SerializedRawTransaction,
+    ) -> Result<TransactionId, Error> {
+        self.route("sendrawtransaction", |node| {
+            node.send_raw_transaction(tx_data.clone())
+        })
+    }
+
+    fn send_to_address(
+        &self,
+        address: &Address,
+        amount: rpc::Amount,
+    ) -> Result<TransactionId, Error> {
+        self.route("sendtoaddress", |node| {
+            node.send_to_address(address, amount)
+        })
+    }
+
+    fn sign_raw_transaction_with_key(
+        &self,
+        tx: &rpc::SerializedRawTransaction,
+        private_keys: Option<Vec<&rpc::PrivateKey>>,
+        dependencies: Option<Vec<&rpc::TransactionOutputDetail>>,
+        signature_hash_type: Option<rpc::SigHashType>,
+    ) -> Result<rpc::SigningResult, Error> {
+        self.route("signrawtransactionwithkey", |node| {
+            node.sign_raw_transaction_with_key(
+                tx,
+                private_keys.clone(),
+                dependencies.clone(),
+                signature_hash_type,
+            )
+        })
+    }
+
+    fn validate_address(&self, address: &Address) -> Result<rpc::AddressValidationResult, Error> {
+        self.route("validateaddress", |node| node.validate_address(address))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mock_server::{MockResponse, MockServer};
+    use serde_json::json;
+    use std::net::TcpListener;
+    use timeouts::Timeouts;
+    use RetryPolicy;
+
+    fn a_node(height: u64) -> MockServer {
+        MockServer::start("user", "password")
+            .unwrap()
+            .with_response(
+                "getblockchaininfo",
+                MockResponse::result(json!({
+                    "chain": "regtest",
+                    "blocks": height,
+                    "headers": height,
+                    "bestblockhash": "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206",
+                    "difficulty": 4.656542373906925e-10,
+                    "mediantime": 1296688602,
+                    "verificationprogress": 1,
+                    "initialblockdownload": false,
+                    "chainwork": "0000000000000000000000000000000000000000000000000000000000000002",
+                    "size_on_disk": 293,
+                    "pruned": false,
+                    "warnings": "",
+                })),
+            )
+            .with_response("getblockcount", MockResponse::result(height))
+    }
+
+    fn a_client(nodes: &[&MockServer]) -> FailoverClient {
+        FailoverClient::new(
+            nodes
+                .iter()
+                .map(|node| {
+                    BitcoinCoreClient::new(&node.url(), "user", "password")
+                        .with_retry_policy(RetryPolicy::none())
+                        .with_timeouts(
+                            Timeouts::new().with_read_timeout(Some(Duration::from_millis(200))),
+                        )
+                })
+                .collect(),
+        )
+    }
+
+    fn a_raw_transaction() -> rpc::SerializedRawTransaction {
+        rpc::SerializedRawTransaction(String::from("0200000000"))
+    }
+
+    fn tip(network: Network, blocks: u64) -> Option<Tip> {
+        Some(Tip { network, blocks })
+    }
+
+    #[test]
+    fn excludes_unreachable_and_lagging_nodes() {
+        let tips = vec![
+            tip(Network::Regtest, 100),
+            None,
+            tip(Network::Regtest, 98),
+            tip(Network::Regtest, 97),
+        ];
+
+        assert_eq!(
+            healthy_nodes(&tips, None, 2),
+            vec![true, false, true, false]
+        );
+    }
+
+    #[test]
+    fn excludes_nodes_on_other_chain() {
+        let tips = vec![
+            tip(Network::Testnet, 500),
+            tip(Network::Bitcoin, 100),
+            tip(Network::Bitcoin, 100),
+        ];
+
+        assert_eq!(healthy_nodes(&tips, None, 0), vec![false, true, true]);
+        assert_eq!(
+            healthy_nodes(&tips, Some(Network::Testnet), 0),
+            vec![true, false, false]
+        );
+        assert_eq!(
+            healthy_nodes(&tips, Some(Network::Regtest), 0),
+            vec![false, false, false]
+        );
+    }
+
+    #[test]
+    fn fails_over_if_node_is_overloaded_or_misconfigured() {
+        for status in &[
+            StatusCode::SERVICE_UNAVAILABLE,
+            StatusCode::UNAUTHORIZED,
+            StatusCode::FORBIDDEN,
+        ] {
+            assert!(is_failover_error(
+                &Error::HttpStatus(*status),
+                "sendtoaddress"
+            ));
+        }
+        assert!(!is_failover_error(
+            &Error::HttpStatus(StatusCode::INTERNAL_SERVER_ERROR),
+            "getblockcount"
+        ));
+    }
+
+    #[test]
+    fn fails_over_if_node_refuses_connection() {
+        let first = a_node(100);
+        let second = a_node(100);
+        let client = a_client(&[&first, &second]);
+        client.check_health();
+
+        drop(first);
+
+        assert_eq!(
+            client.get_block_count().unwrap(),
+            rpc::BlockHeight::new(100)
+        );
+        assert!(!client.is_healthy(0));
+        assert!(client.is_healthy(1));
+    }
+
+    #[test]
+    fn fails_over_idempotent_rpcs_after_timeout() {
+        let delayed = MockResponse::result(7).delayed(Duration::from_secs(2));
+        let first = a_node(100).with_response("getblockcount", delayed);
+        let second = a_node(100);
+        let client = a_client(&[&first, &second]);
+
+        assert_eq!(
+            client.get_block_count().unwrap(),
+            rpc::BlockHeight::new(100)
+        );
+    }
+
+    #[test]
+    fn does_not_fail_over_non_idempotent_rpcs_after_timeout() {
+        let txid = "7e7c52b1f46e7ea2511e885d8c0e5df9297f65b6fff6907ceb1377d0582e45f4";
+        let first = a_node(100).with_response(
+            "sendrawtransaction",
+            MockResponse::result(txid).delayed(Duration::from_secs(2)),
+        );
+        let second = a_node(100).with_response("sendrawtransaction", MockResponse::result(txid));
+        let client = a_client(&[&first, &second]);
+
+        match client.send_raw_transaction(a_raw_transaction()) {
+            Err(Error::Timeout(_)) => {}
+            outcome => panic!("expected a timeout but got {:?}", outcome),
+        }
+        assert!(!second
+            .received_methods()
+            .contains(&String::from("sendrawtransaction")));
+    }
+
+    #[test]
+    fn fails_without_healthy_node() {
+        let lagging = a_node(90);
+        let unreachable = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let client = FailoverClient::new(vec![
+            BitcoinCoreClient::new(&format!("http://{}", unreachable), "user", "password")
+                .with_retry_policy(RetryPolicy::none()),
+            BitcoinCoreClient::new(&lagging.url(), "user", "password"),
+        ])
+        .with_network(Network::Bitcoin);
+
+        match client.get_block_count() {
+            Err(Error::NoHealthyNode) => {}
+            outcome => panic!("expected no healthy node but got {:?}", outcome),
+        }
+        assert_eq!(
+            lagging.received_methods(),
+            vec![String::from("getblockchaininfo")]
+        );
+    }
+
+    #[test]
+    fn refuses_wallet_rpcs_if_first_node_is_unhealthy() {
+        let first = a_node(90).with_response("getbalance", MockResponse::result(1.0));
+        let second = a_node(100).with_response("getbalance", MockResponse::result(1.5));
+        let client = a_client(&[&first, &second]);
+
+        match client.get_balance() {
+            Err(Error::NoHealthyNode) => {}
+            outcome => panic!("expected no healthy node but got {:?}", outcome),
+        }
+        assert!(!client.is_healthy(0));
+        for node in &[&first, &second] {
+            assert!(!node
+                .received_methods()
+                .contains(&String::from("getbalance")));
+        }
+    }
+
+    #[test]
+    fn does_not_fail_over_wallet_rpcs() {
+        let first = a_node(100).with_response("getbalance", MockResponse::Status(503));
+        let second = a_node(100).with_response("getbalance", MockResponse::result(1.5));
+        let client = a_client(&[&first, &second]);
+
+        match client.get_balance() {
+            Err(Error::HttpStatus(StatusCode::SERVICE_UNAVAILABLE)) => {}
+            outcome => panic!("expected 503 but got {:?}", outcome),
+        }
+        assert!(!client.is_healthy(0));
+        assert!(!second
+            .received_methods()
+            .contains(&String::from("getbalance")));
+    }
+
+    #[test]
+    fn prefers_network_of_first_node_on_tie() {
+        let tips = vec![tip(Network::Testnet, 1), tip(Network::Bitcoin, 1)];
+
+        assert_eq!(healthy_nodes(&tips, None, 0), vec![true, false]);
+    }
+}
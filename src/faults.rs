@@ -0,0 +1,493 @@
+use bitcoin::{Address, Script};
+use bitcoincore::deserialize_response;
+use error::Error;
+use error_code::RpcErrorCode;
+use jsonrpc_client::RpcError;
+use payload::ids;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use reqwest::StatusCode;
+use rpc;
+use serde::de::DeserializeOwned;
+use serde_json::{self, json, Value};
+use std::{collections::HashSet, sync::Mutex, thread, time::Duration};
+use types::address::AddressInfoResult;
+use BitcoinRpcApi;
+use BlockHash;
+use TransactionId;
+
+/// A way in which a flaky node fails a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Fault {
+    /// The node answers with RPC error -28 as it does while starting up.
+    WarmingUp,
+    /// The node answers with HTTP status 503 as it does when its work queue is full.
+    ServiceUnavailable,
+    /// The connection is reset before the node answers.
+    ConnectionReset,
+    /// The response is cut off and not valid JSON.
+    MalformedJson,
+}
+
+/// Probabilities like 0.1 + 0.2 don't add up to exactly 0.3.
+const MAX_ROUNDING_ERROR: f64 = 1e-9;
+
+/// Which faults to inject into requests, and how often.
+///
+/// Faults are drawn from an RNG seeded with `seed`, so the same calls in the same order
+/// always fail the same way.
+pub struct Faults {
+    faults: Vec<(Fault, f64)>,
+    latency: Option<(Duration, Duration)>,
+    methods: Option<HashSet<String>>,
+    rng: Mutex<StdRng>,
+}
+
+impl Faults {
+    pub fn new(seed: u64) -> Self {
+        Faults {
+            faults: Vec::new(),
+            latency: None,
+            methods: None,
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+
+    /// Fails requests with `fault` with the given probability between 0 and 1.
+    ///
+    /// Panics if the probabilities of all faults add up to more than 1.
+    pub fn with_fault(mut self, fault: Fault, probability: f64) -> Self {
+        let total = self.faults.iter().map(|&(_, p)| p).sum::<f64>() + probability;
+        assert!(
+            probability >= 0.0 && total <= 1.0 + MAX_ROUNDING_ERROR,
+            "probability of {:?} must be at least 0 and all faults at most 1, got {} and {}",
+            fault,
+            probability,
+            total
+        );
+
+        self.faults.push((fault, probability));
+        self
+    }
+
+    /// Delays every request by a random duration between `min` and `max`.
+    ///
+    /// Panics if `min` is greater than `max`.
+    pub fn with_latency(self, min: Duration, max: Duration) -> Self {
+        assert!(
+            min <= max,
+            "minimum latency {:?} is greater than maximum {:?}",
+            min,
+            max
+        );
+
+        Faults {
+            latency: Some((min, max)),
+            ..self
+        }
+    }
+
+    /// Only injects faults and latency into requests of the given RPC methods, e.g.
+    /// `getblockcount`. By default, all requests are affected.
+    pub fn with_methods(self, methods: &[&str]) -> Self {
+        Faults {
+            methods: Some(methods.iter().map(|method| method.to_string()).collect()),
+            ..self
+        }
+    }
+
+    /// Waits for the injected latency and draws the fault for a request of `methods`, if any.
+    pub(crate) fn next<'a, I: IntoIterator<Item = &'a str>>(&self, methods: I) -> Option<Fault> {
+        let selected = match self.methods {
+            Some(ref selected) => methods.into_iter().any(|method| selected.contains(method)),
+            None => true,
+        };
+        if !selected {
+            return None;
+        }
+
+        let (delay, draw) = {
+            let mut rng = self.rng.lock().unwrap();
+            let delay = self
+                .latency
+                .map(|(min, max)| min + (max - min).mul_f64(rng.gen::<f64>()));
+
+            (delay, rng.gen::<f64>())
+        };
+
+        if let Some(delay) = delay {
+            thread::sleep(delay);
+        }
+
+        let mut cumulative = 0.0;
+        self.faults.iter().find_map(|&(fault, probability)| {
+            cumulative += probability;
+
+            if draw < cumulative {
+                Some(fault)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+impl Fault {
+    /// The error `BitcoinCoreClient` returns for a request to `method` failing this way.
+    pub(crate) fn into_error(self, method: &str) -> Error {
+        match self {
+            Fault::WarmingUp => Error::Rpc(warming_up()),
+            Fault::ServiceUnavailable => Error::HttpStatus(StatusCode::SERVICE_UNAVAILABLE),
+            Fault::ConnectionReset => Error::ConnectionReset,
+            Fault::MalformedJson => deserialize_response::<Value>(method, MALFORMED_JSON)
+                .expect_err("malformed JSON does not deserialize"),
+        }
+    }
+
+    /// The response `BitcoinCoreClient` gets for `payload` failing this way, before retrying.
+    pub(crate) fn into_response<R: DeserializeOwned>(
+        self,
+        method: &str,
+        payload: &str,
+    ) -> Result<R, Error> {
+        match self {
+            Fault::WarmingUp => deserialize_response(method, &warming_up_response(payload)),
+            Fault::MalformedJson => deserialize_response(method, MALFORMED_JSON),
+            fault => Err(fault.into_error(method)),
+        }
+    }
+}
+
+fn warming_up_response(payload: &str) -> Vec<u8> {
+    let payload = serde_json::from_str(payload).unwrap_or(Value::Null);
+    let response = |id: &str| {
+        let rpc_error = warming_up();
+
+        json!({
+            "result": null,
+            "error": {"code": rpc_error.code, "message": rpc_error.message},
+            "id": id,
+        })
+    };
+    let ids = ids(&payload);
+
+    let body = match payload {
+        Value::Array(_) => Value::Array(ids.into_iter().map(response).collect()),
+        _ => response(ids.first().cloned().unwrap_or_default()),
+    };
+
+    body.to_string().into_bytes()
+}
+
+pub(crate) const MALFORMED_JSON: &[u8] = br#"{"result":{"chain":"regt"#;
+
+pub(crate) fn warming_up() -> RpcError {
+    RpcError {
+        code: RpcErrorCode::InWarmup.code(),
+        message: String::from("Loading block index..."),
+    }
+}
+
+/// Wraps a `BitcoinRpcApi` and fails its calls like a flaky node would.
+///
+/// Use `BitcoinCoreClient::with_faults` instead to inject faults below the retry logic of
+/// the client.
+pub struct FaultInjector<R> {
+    inner: R,
+    faults: Faults,
+}
+
+impl<R: BitcoinRpcApi> FaultInjector<R> {
+    pub fn new(inner: R, faults: Faults) -> Self {
+        FaultInjector { inner, faults }
+    }
+
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    fn inject(&self, method: &str) -> Result<(), Error> {
+        match self.faults.next(Some(method)) {
+            Some(fault) => Err(fault.into_error(method)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<R: BitcoinRpcApi> BitcoinRpcApi for FaultInjector<R> {
+    fn add_multisig_address(
+        &self,
+        number_of_required_signatures: u32,
+        participants: Vec<&Address>,
+    ) -> Result<rpc::MultiSigAddress, Error> {
+        self.inject("addmultisigaddress")?;
+        self.inner
+            .add_multisig_address(number_of_required_signatures, participants)
+    }
+
+    fn create_raw_transaction(
+        &self,
+        inputs: Vec<&rpc::NewTransactionInput>,
+        output: &rpc::NewTransactionOutput,
+    ) -> Result<rpc::SerializedRawTransaction, Error> {
+        self.inject("createrawtransaction")?;
+        self.inner.create_raw_transaction(inputs, output)
+    }
+
+    fn decode_rawtransaction(
+        &self,
+        tx: rpc::SerializedRawTransaction,
+    ) -> Result<rpc::DecodedRawTransaction, Error> {
+        self.inject("decoderawtransaction")?;
+        self.inner.decode_rawtransaction(tx)
+    }
+
+    fn decode_script(&self, script: Script) -> Result<rpc::DecodedScript, Error> {
+        self.inject("decodescript")?;
+        self.inner.decode_script(script)
+    }
+
+    fn dump_privkey(&self, address: &Address) -> Result<rpc::PrivateKey, Error> {
+        self.inject("dumpprivkey")?;
+        self.inner.dump_privkey(address)
+    }
+
+    fn fund_raw_transaction(
+        &self,
+        tx: &rpc::SerializedRawTransaction,
+        options: &rpc::FundingOptions,
+    ) -> Result<rpc::FundingResult, Error> {
+        self.inject("fundrawtransaction")?;
+        self.inner.fund_raw_transaction(tx, options)
+    }
+
+    fn generate(&self, number_of_blocks: u32) -> Result<Vec<BlockHash>, Error> {
+        self.inject("generate")?;
+        self.inner.generate(number_of_blocks)
+    }
+
+    fn get_address_info(&self, address: &Address) -> Result<AddressInfoResult, Error> {
+        self.inject("getaddressinfo")?;
+        self.inner.get_address_info(address)
+    }
+
+    fn get_balance(&self) -> Result<rpc::Amount, Error> {
+        self.inject("getbalance")?;
+        self.inner.get_balance()
+    }
+
+    fn get_best_block_hash(&self) -> Result<BlockHash, Error> {
+        self.inject("getbestblockhash")?;
+        self.inner.get_best_block_hash()
+    }
+
+    fn get_block(&self, header_hash: &BlockHash) -> Result<rpc::Block<TransactionId>, Error> {
+        self.inject("getblock")?;
+        self.inner.get_block(header_hash)
+    }
+
+    fn get_block_verbose(
+        &self,
+        header_hash: &BlockHash,
+    ) -> Result<rpc::Block<rpc::DecodedRawTransaction>, Error> {
+        self.inject("getblock")?;
+        self.inner.get_block_verbose(header_hash)
+    }
+
+    fn get_blockchain_info(&self) -> Result<rpc::BlockchainInfo, Error> {
+        self.inject("getblockchaininfo")?;
+        self.inner.get_blockchain_info()
+    }
+
+    fn get_block_count(&self) -> Result<rpc::BlockHeight, Error> {
+        self.inject("getblockcount")?;
+        self.inner.get_block_count()
+    }
+
+    fn get_block_hash(&self, height: u32) -> Result<BlockHash, Error> {
+        self.inject("getblockhash")?;
+        self.inner.get_block_hash(height)
+    }
+
+    fn get_new_address(&self) -> Result<Address, Error> {
+        self.inject("getnewaddress")?;
+        self.inner.get_new_address()
+    }
+
+    fn get_raw_transaction_serialized(
+        &self,
+        tx: &TransactionId,
+    ) -> Result<rpc::SerializedRawTransaction, Error> {
+        self.inject("getrawtransaction")?;
+        self.inner.get_raw_transaction_serialized(tx)
+    }
+
+    fn get_raw_transaction_verbose(
+        &self,
+        tx: &TransactionId,
+    ) -> Result<rpc::VerboseRawTransaction, Error> {
+        self.inject("getrawtransaction")?;
+        self.inner.get_raw_transaction_verbose(tx)
+    }
+
+    fn list_unspent(
+        &self,
+        min_confirmations: rpc::TxOutConfirmations,
+        max_confirmations: Option<u32>,
+        recipients: Option<Vec<Address>>,
+    ) -> Result<Vec<rpc::UnspentTransactionOutput>, Error> {
+        self.inject("listunspent")?;
+        self.inner
+            .list_unspent(min_confirmations, max_confirmations, recipients)
+    }
+
+    fn send_raw_transaction(
+        &self,
+        tx_data: rpc::SerializedRawTransaction,
+    ) -> Result<TransactionId, Error> {
+        self.inject("sendrawtransaction")?;
+        self.inner.send_raw_transaction(tx_data)
+    }
+
+    fn send_to_address(
+        &self,
+        address: &Address,
+        amount: rpc::Amount,
+    ) -> Result<TransactionId, Error> {
+        self.inject("sendtoaddress")?;
+        self.inner.send_to_address(address, amount)
+    }
+
+    fn sign_raw_transaction_with_key(
+        &self,
+        tx: &rpc::SerializedRawTransaction,
+        private_keys: Option<Vec<&rpc::PrivateKey>>,
+        dependencies: Option<Vec<&rpc::TransactionOutputDetail>>,
+        signature_hash_type: Option<rpc::SigHashType>,
+    ) -> Result<rpc::SigningResult, Error> {
+        self.inject("signrawtransactionwithkey")?;
+        self.inner.sign_raw_transaction_with_key(
+            tx,
+            private_keys,
+            dependencies,
+            signature_hash_type,
+        )
+    }
+
+    fn validate_address(&self, address: &Address) -> Result<rpc::AddressValidationResult, Error> {
+        self.inject("validateaddress")?;
+        self.inner.validate_address(address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use stub_rpc_client::BitcoinStubClient;
+
+    fn outcomes(faults: &Faults, method: &str) -> Vec<Option<Fault>> {
+        (0..20).map(|_| faults.next(Some(method))).collect()
+    }
+
+    #[test]
+    fn same_seed_injects_same_faults() {
+        let faults = || {
+            Faults::new(42)
+                .with_fault(Fault::WarmingUp, 0.3)
+                .with_fault(Fault::ServiceUnavailable, 0.3)
+        };
+
+        let injected = outcomes(&faults(), "getblockcount");
+
+        assert_eq!(injected, outcomes(&faults(), "getblockcount"));
+        assert!(injected.contains(&None));
+        assert!(injected.contains(&Some(Fault::WarmingUp)));
+        assert!(injected.contains(&Some(Fault::ServiceUnavailable)));
+    }
+
+    #[test]
+    fn only_injects_into_selected_methods() {
+        let faults = Faults::new(1)
+            .with_fault(Fault::MalformedJson, 1.0)
+            .with_methods(&["getblockcount"]);
+
+        assert_eq!(faults.next(Some("getbestblockhash")), None);
+        assert_eq!(
+            faults.next(Some("getblockcount")),
+            Some(Fault::MalformedJson)
+        );
+    }
+
+    #[test]
+    fn delays_requests_by_at_least_minimum_latency() {
+        let faults =
+            Faults::new(3).with_latency(Duration::from_millis(20), Duration::from_millis(40));
+
+        for _ in 0..3 {
+            let started = Instant::now();
+            assert_eq!(faults.next(Some("getblockcount")), None);
+            let elapsed = started.elapsed();
+
+            assert!(elapsed >= Duration::from_millis(20), "{:?}", elapsed);
+        }
+
+        let started = Instant::now();
+        Faults::new(3)
+            .with_latency(Duration::from_millis(5), Duration::from_millis(5))
+            .next(Some("getblockcount"));
+        assert!(started.elapsed() >= Duration::from_millis(5));
+    }
+
+    #[test]
+    #[should_panic(expected = "minimum latency")]
+    fn rejects_minimum_latency_above_maximum() {
+        Faults::new(1).with_latency(Duration::from_secs(2), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn accepts_probabilities_adding_up_to_1() {
+        let faults = Faults::new(5)
+            .with_fault(Fault::WarmingUp, 0.1)
+            .with_fault(Fault::ServiceUnavailable, 0.2)
+            .with_fault(Fault::MalformedJson, 0.7);
+
+        assert!(outcomes(&faults, "getblockcount")
+            .iter()
+            .all(Option::is_some));
+    }
+
+    #[test]
+    #[should_panic(expected = "at most 1")]
+    fn rejects_probabilities_adding_up_to_more_than_1() {
+        Faults::new(1)
+            .with_fault(Fault::WarmingUp, 0.6)
+            .with_fault(Fault::ServiceUnavailable, 0.6);
+    }
+
+    #[test]
+    fn fails_calls_like_a_flaky_node() {
+        let stub = BitcoinStubClient::new().with_result("getblockcount", rpc::BlockHeight::new(1));
+        let injector = FaultInjector::new(stub, Faults::new(7).with_fault(Fault::WarmingUp, 1.0));
+
+        let error = injector.get_block_count().unwrap_err();
+
+        assert_eq!(error.rpc_error_code(), Some(RpcErrorCode::InWarmup));
+        assert_eq!(injector.inner().call_count("getblockcount"), 0);
+    }
+
+    #[test]
+    fn turns_faults_into_client_errors() {
+        match Fault::ConnectionReset.into_error("getblockcount") {
+            Error::ConnectionReset => {}
+            error => panic!("expected a connection reset but got {:?}", error),
+        }
+        match Fault::MalformedJson.into_error("getblockchaininfo") {
+            Error::Deserialization { ref method, .. } if method == "getblockchaininfo" => {}
+            error => panic!("expected a deserialization error but got {:?}", error),
+        }
+        match Fault::ServiceUnavailable.into_error("getblockcount") {
+            Error::HttpStatus(StatusCode::SERVICE_UNAVAILABLE) => {}
+            error => panic!("expected HTTP status 503 but got {:?}", error),
+        }
+    }
+}
@@ -0,0 +1,320 @@
+use error::Error;
+use reqwest::StatusCode;
+use serde::de;
+use serde_json::{self, Value};
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// A request sent to the node and the response it answered with, as stored in a fixture file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct Exchange {
+    request: Value,
+    /// The HTTP status of the response, only recorded if it isn't 200 OK.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    response: Option<Value>,
+    /// The body of a response that isn't JSON, e.g. bitcoind's "Work queue depth exceeded".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    raw_response: Option<String>,
+}
+
+impl Exchange {
+    fn status(&self) -> Result<StatusCode, Error> {
+        match self.status {
+            Some(status) => StatusCode::from_u16(status).map_err(|_| {
+                Error::Json(de::Error::custom(format!("invalid HTTP status {}", status)))
+            }),
+            None => Ok(StatusCode::OK),
+        }
+    }
+}
+
+/// Where the requests and responses of a `BitcoinCoreClient` go when it records or replays.
+pub(crate) enum Fixture {
+    Recording {
+        path: PathBuf,
+        exchanges: Mutex<Vec<Exchange>>,
+    },
+    Replaying {
+        exchanges: Vec<Exchange>,
+        used: Mutex<Vec<bool>>,
+    },
+}
+
+impl Fixture {
+    pub(crate) fn recording<P: AsRef<Path>>(path: P) -> Self {
+        Fixture::Recording {
+            path: path.as_ref().to_path_buf(),
+            exchanges: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn replaying<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let exchanges: Vec<Exchange> = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+        let used = vec![false; exchanges.len()];
+
+        Ok(Fixture::Replaying {
+            exchanges,
+            used: Mutex::new(used),
+        })
+    }
+
+    /// The recorded status and body of the response to `payload`, `None` if not replaying.
+    ///
+    /// Requests match if they only differ in their ids. If several recorded requests match,
+    /// their responses are returned in the recorded order.
+    pub(crate) fn replay(&self, payload: &str) -> Option<Result<(StatusCode, String), Error>> {
+        let (exchanges, used) = match *self {
+            Fixture::Replaying {
+                ref exchanges,
+                ref used,
+            } => (exchanges, used),
+            Fixture::Recording { .. } => return None,
+        };
+
+        let request = match serde_json::from_str(payload) {
+            Ok(request) => request,
+            Err(e) => return Some(Err(Error::Json(e))),
+        };
+        let mut used = used.lock().unwrap();
+        let index = (0..exchanges.len()).find(|&index| {
+            !used[index] && without_ids(&exchanges[index].request) == without_ids(&request)
+        });
+
+        Some(match index {
+            Some(index) => {
+                used[index] = true;
+                let exchange = &exchanges[index];
+                let body = match exchange.response {
+                    Some(ref response) => {
+                        with_ids(response, &ids(&exchange.request), &ids(&request)).to_string()
+                    }
+                    None => exchange.raw_response.clone().unwrap_or_default(),
+                };
+
+                exchange.status().map(|status| (status, body))
+            }
+            None => Err(Error::UnexpectedRequest {
+                request: payload.to_string(),
+            }),
+        })
+    }
+
+    /// Appends the exchange to the fixture file if recording.
+    ///
+    /// Bodies that aren't JSON and error statuses are recorded as they are, so replaying fails
+    /// the same way. The file is rewritten after every exchange so it is complete even if the
+    /// test panics.
+    pub(crate) fn record(
+        &self,
+        payload: &str,
+        status: StatusCode,
+        body: &str,
+    ) -> Result<(), Error> {
+        let (path, exchanges) = match *self {
+            Fixture::Recording {
+                ref path,
+                ref exchanges,
+            } => (path, exchanges),
+            Fixture::Replaying { .. } => return Ok(()),
+        };
+
+        let response = serde_json::from_str(body).ok();
+        let raw_response = match response {
+            Some(_) => None,
+            None if body.is_empty() => None,
+            None => Some(body.to_string()),
+        };
+        let mut exchanges = exchanges.lock().unwrap();
+
+        exchanges.push(Exchange {
+            request: serde_json::from_str(payload)?,
+            status: if status == StatusCode::OK {
+                None
+            } else {
+                Some(status.as_u16())
+            },
+            response,
+            raw_response,
+        });
+
+        let file = File::create(path).map_err(Error::Io)?;
+        serde_json::to_writer_pretty(BufWriter::new(file), &*exchanges)?;
+
+        Ok(())
+    }
+}
+
+/// The ids of a request or of all requests in a batch, in order.
+fn ids(message: &Value) -> Vec<Value> {
+    match *message {
+        Value::Array(ref messages) => messages.iter().flat_map(ids).collect(),
+        Value::Object(ref fields) => fields.get("id").cloned().into_iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn without_ids(message: &Value) -> Value {
+    match *message {
+        Value::Array(ref messages) => Value::Array(messages.iter().map(without_ids).collect()),
+        Value::Object(ref fields) => {
+            let mut fields = fields.clone();
+            fields.remove("id");
+
+            Value::Object(fields)
+        }
+        ref message => message.clone(),
+    }
+}
+
+/// Replaces the recorded ids in a response with the ids of the requests sent now.
+fn with_ids(response: &Value, recorded: &[Value], actual: &[Value]) -> Value {
+    match *response {
+        Value::Array(ref responses) => Value::Array(
+            responses
+                .iter()
+                .map(|response| with_ids(response, recorded, actual))
+                .collect(),
+        ),
+        Value::Object(ref fields) => {
+            let mut fields = fields.clone();
+
+            if let Some(id) = fields.get_mut("id") {
+                if let Some(index) = recorded.iter().position(|recorded| recorded == id) {
+                    if let Some(actual) = actual.get(index) {
+                        *id = actual.clone();
+                    }
+                }
+            }
+
+            Value::Object(fields)
+        }
+        ref response => response.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::{env, fs, process};
+
+    fn fixture_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("{}-{}.json", name, process::id()))
+    }
+
+    #[test]
+    fn replays_recorded_exchanges_with_current_ids() {
+        let path = fixture_path("replays_recorded_exchanges_with_current_ids");
+        let recording = Fixture::recording(&path);
+
+        recording
+            .record(
+                r#"{"jsonrpc":"1.0","id":"1","method":"getblockcount","params":[]}"#,
+                StatusCode::OK,
+                r#"{"result":100,"error":null,"id":"1"}"#,
+            )
+            .unwrap();
+        recording
+            .record(
+                r#"{"jsonrpc":"1.0","id":"2","method":"getblockcount","params":[]}"#,
+                StatusCode::OK,
+                r#"{"result":101,"error":null,"id":"2"}"#,
+            )
+            .unwrap();
+
+        let replaying = Fixture::replaying(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let replay = |payload| replaying.replay(payload).unwrap().unwrap();
+        let request = r#"{"jsonrpc":"1.0","id":"7","method":"getblockcount","params":[]}"#;
+
+        assert_eq!(
+            replay(request),
+            (
+                StatusCode::OK,
+                String::from(r#"{"error":null,"id":"7","result":100}"#)
+            )
+        );
+        assert_eq!(
+            replay(request),
+            (
+                StatusCode::OK,
+                String::from(r#"{"error":null,"id":"7","result":101}"#)
+            )
+        );
+        assert!(recording.replay(request).is_none());
+    }
+
+    #[test]
+    fn replays_error_statuses_and_bodies_that_are_not_json() {
+        let path = fixture_path("replays_error_statuses_and_bodies_that_are_not_json");
+        let recording = Fixture::recording(&path);
+        let request = r#"{"jsonrpc":"1.0","id":"1","method":"getblockcount","params":[]}"#;
+
+        recording
+            .record(
+                request,
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Work queue depth exceeded",
+            )
+            .unwrap();
+        recording
+            .record(request, StatusCode::UNAUTHORIZED, "")
+            .unwrap();
+        recording
+            .record(request, StatusCode::OK, "<html>Bad Gateway</html>")
+            .unwrap();
+
+        let replaying = Fixture::replaying(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let replay = || replaying.replay(request).unwrap().unwrap();
+
+        assert_eq!(
+            replay(),
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                String::from("Work queue depth exceeded")
+            )
+        );
+        assert_eq!(replay(), (StatusCode::UNAUTHORIZED, String::new()));
+        assert_eq!(
+            replay(),
+            (StatusCode::OK, String::from("<html>Bad Gateway</html>"))
+        );
+    }
+
+    #[test]
+    fn fails_on_unexpected_request() {
+        let replaying = Fixture::Replaying {
+            exchanges: Vec::new(),
+            used: Mutex::new(Vec::new()),
+        };
+
+        match replaying.replay(r#"{"jsonrpc":"1.0","id":"1","method":"getblockcount","params":[]}"#)
+        {
+            Some(Err(Error::UnexpectedRequest { .. })) => {}
+            outcome => panic!("expected unexpected request but got {:?}", outcome),
+        }
+    }
+
+    #[test]
+    fn maps_ids_of_batches() {
+        let response = json!([{"id": "2", "result": 1}, {"id": "1", "result": 2}]);
+
+        assert_eq!(
+            with_ids(
+                &response,
+                &[json!("1"), json!("2")],
+                &[json!("10"), json!("11")]
+            ),
+            json!([{"id": "11", "result": 1}, {"id": "10", "result": 2}])
+        );
+    }
+}
@@ -4,19 +4,53 @@
 extern crate base64;
 extern crate bitcoin;
 extern crate bitcoin_hashes;
+extern crate futures;
 extern crate hex as std_hex;
 extern crate jsonrpc_client;
 #[macro_use]
 extern crate log;
+#[cfg(test)]
+#[macro_use]
+extern crate quickcheck;
+extern crate rand;
+extern crate reqwest;
+#[cfg(feature = "test-support")]
+extern crate secp256k1;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde_json;
+extern crate serde_path_to_error;
+extern crate tokio_timer;
 
+mod auth;
 mod bitcoin_rpc_api;
+mod bitcoin_rpc_api_async;
 mod bitcoincore;
+mod bitcoincore_async;
+mod config;
+mod error;
+mod error_code;
+mod failover;
+#[cfg(feature = "test-support")]
+mod faults;
+mod fixture;
+#[cfg(feature = "test-support")]
+mod mock_server;
+mod payload;
+#[cfg(feature = "regtest")]
+mod regtest;
+mod reject_reason;
+mod retry;
+#[cfg(feature = "test-support")]
+mod simulator;
 mod stub_rpc_client;
+#[cfg(any(feature = "regtest", feature = "test-support"))]
+mod test_client;
+mod timeouts;
 mod types;
+mod version;
+mod wallet;
 
 // Re-export types from rust-bitcoin crates so explicit dependency is not needed
 pub type TransactionId = bitcoin_hashes::sha256d::Hash;
@@ -27,14 +61,36 @@ pub use bitcoin::util::key::PrivateKey;
 pub use bitcoin::Address;
 pub use bitcoin::Script;
 
+pub use auth::Auth;
 pub use bitcoin_rpc_api::BitcoinRpcApi;
+pub use bitcoin_rpc_api_async::{AsyncBitcoinRpcApi, RpcFuture};
 pub use bitcoincore::BitcoinCoreClient;
-pub use stub_rpc_client::BitcoinStubClient;
+pub use bitcoincore_async::AsyncBitcoinCoreClient;
+pub use config::{default_datadir, default_rpc_port, ConfigError, RpcConfig};
+pub use error::Error;
+pub use error_code::RpcErrorCode;
+pub use failover::FailoverClient;
+#[cfg(feature = "test-support")]
+pub use faults::{Fault, FaultInjector, Faults};
+#[cfg(feature = "test-support")]
+pub use mock_server::{MockResponse, MockServer, ReceivedRequest};
+#[cfg(feature = "regtest")]
+pub use regtest::{RegtestError, RegtestNode};
+pub use reject_reason::RejectReason;
+pub use retry::RetryPolicy;
+#[cfg(feature = "test-support")]
+pub use simulator::RegtestSimulator;
+pub use stub_rpc_client::{BitcoinStubClient, StubCall};
+#[cfg(any(feature = "regtest", feature = "test-support"))]
+pub use test_client::BitcoinCoreTestClient;
+pub use timeouts::Timeouts;
+pub use version::NodeVersion;
 
-pub use jsonrpc_client::{ClientError, RpcError};
+pub use jsonrpc_client::{JsonRpcVersion, RpcError, RpcRequest};
 
 pub mod rpc {
     pub use types::address::*;
+    pub use types::amount::*;
     pub use types::block::*;
     pub use types::blockchain::*;
     pub use types::keys::*;
@@ -0,0 +1,25 @@
+use serde_json::Value;
+
+/// The methods of a serialized request or of all requests of a serialized batch.
+pub(crate) fn methods(payload: &Value) -> Vec<&str> {
+    match *payload {
+        Value::Array(ref requests) => requests.iter().flat_map(methods).collect(),
+        _ => payload
+            .get("method")
+            .and_then(Value::as_str)
+            .into_iter()
+            .collect(),
+    }
+}
+
+/// The ids of a serialized request or of all requests of a serialized batch.
+pub(crate) fn ids(payload: &Value) -> Vec<&str> {
+    match *payload {
+        Value::Array(ref requests) => requests.iter().flat_map(ids).collect(),
+        _ => payload
+            .get("id")
+            .and_then(Value::as_str)
+            .into_iter()
+            .collect(),
+    }
+}
@@ -0,0 +1,294 @@
+use bitcoin::Address;
+use bitcoincore::BitcoinCoreClient;
+use error::Error;
+use retry::RetryPolicy;
+use rpc;
+use serde_json::{json, Value};
+use std::{
+    env,
+    error::Error as StdError,
+    ffi::OsString,
+    fmt, fs, io,
+    net::TcpListener,
+    path::{Path, PathBuf},
+    process::{self, Child, Command, ExitStatus, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+use test_client::BitcoinCoreTestClient;
+use version::NodeVersion;
+use BitcoinRpcApi;
+use BlockHash;
+use TransactionId;
+
+const USERNAME: &str = "regtest";
+const PASSWORD: &str = "regtest";
+
+/// How long bitcoind may take to load and answer RPCs.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Nodes started by this process, to give each one its own datadir.
+static NODES: AtomicUsize = AtomicUsize::new(0);
+
+/// A `bitcoind -regtest` process started for a test, with a wallet ready to use.
+///
+/// The node gets a fresh datadir in the temp directory and free ports, so tests can run
+/// in parallel. It is stopped and its datadir deleted when the `RegtestNode` is dropped.
+///
+/// The binary is taken from `BITCOIND_EXE` if set, otherwise `bitcoind` is looked up in `PATH`.
+pub struct RegtestNode {
+    process: Child,
+    datadir: PathBuf,
+    url: String,
+    client: BitcoinCoreClient,
+}
+
+#[derive(Debug)]
+pub enum RegtestError {
+    Io(io::Error),
+    /// bitcoind exited before it was ready, e.g. because of an invalid option.
+    Exited(ExitStatus),
+    /// bitcoind did not answer RPCs within the startup timeout.
+    NotReady(Error),
+}
+
+impl RegtestNode {
+    pub fn start() -> Result<Self, RegtestError> {
+        Self::start_with_args(&[])
+    }
+
+    /// Passes `args` to bitcoind in addition to the options of the harness, e.g.
+    /// `-deprecatedrpc=create_bdb`.
+    pub fn start_with_args(args: &[&str]) -> Result<Self, RegtestError> {
+        let bitcoind = env::var_os("BITCOIND_EXE").unwrap_or_else(|| OsString::from("bitcoind"));
+
+        Self::spawn(bitcoind, args)
+    }
+
+    pub fn start_with_bitcoind<P: AsRef<Path>>(bitcoind: P) -> Result<Self, RegtestError> {
+        Self::spawn(bitcoind, &[])
+    }
+
+    fn spawn<P: AsRef<Path>>(bitcoind: P, args: &[&str]) -> Result<Self, RegtestError> {
+        let datadir = env::temp_dir().join(format!(
+            "bitcoind-regtest-{}-{}",
+            process::id(),
+            NODES.fetch_add(1, Ordering::Relaxed)
+        ));
+        if datadir.exists() {
+            fs::remove_dir_all(&datadir)?;
+        }
+        fs::create_dir_all(&datadir)?;
+
+        // The ports are only reserved until bitcoind binds them, another process could take
+        // them in between. This is unlikely enough for tests.
+        let rpc_port = free_port()?;
+        let p2p_port = free_port()?;
+
+        debug!(
+            "Starting {} in {} with RPC on port {}",
+            bitcoind.as_ref().display(),
+            datadir.display(),
+            rpc_port
+        );
+        let process = Command::new(bitcoind.as_ref())
+            .arg("-regtest")
+            .arg(format!("-datadir={}", datadir.display()))
+            .arg(format!("-rpcport={}", rpc_port))
+            .arg(format!("-port={}", p2p_port))
+            .arg(format!("-rpcuser={}", USERNAME))
+            .arg(format!("-rpcpassword={}", PASSWORD))
+            .arg("-server")
+            .arg("-listen=0")
+            .arg("-txindex")
+            .arg("-fallbackfee=0.0002")
+            .arg("-printtoconsole=0")
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let process = match process {
+            Ok(process) => process,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&datadir);
+                return Err(RegtestError::Io(e));
+            }
+        };
+
+        let url = format!("http://127.0.0.1:{}", rpc_port);
+        let mut node = RegtestNode {
+            process,
+            datadir,
+            client: BitcoinCoreClient::new(&url, USERNAME, PASSWORD),
+            url,
+        };
+
+        node.wait_until_ready()?;
+        node.create_wallet()?;
+
+        Ok(node)
+    }
+
+    /// A client connected to the node, with the default `RetryPolicy`.
+    pub fn client(&self) -> &BitcoinCoreClient {
+        &self.client
+    }
+
+    /// A new client connected to the node, e.g. to record a fixture `with_recording`.
+    pub fn new_client(&self) -> BitcoinCoreClient {
+        BitcoinCoreClient::new(&self.url, USERNAME, PASSWORD)
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn datadir(&self) -> &Path {
+        &self.datadir
+    }
+
+    /// Helpers creating blocks, transactions and addresses with the client of the node.
+    pub fn test_client<'a>(&'a self) -> BitcoinCoreTestClient<'a> {
+        BitcoinCoreTestClient::new(&self.client)
+    }
+
+    pub fn a_utxo(&self) -> rpc::UnspentTransactionOutput {
+        self.test_client().a_utxo()
+    }
+
+    pub fn a_transaction_id(&self) -> TransactionId {
+        self.test_client().a_transaction_id()
+    }
+
+    pub fn a_block_hash(&self) -> BlockHash {
+        self.test_client().a_block_hash()
+    }
+
+    pub fn an_address(&self) -> Address {
+        self.test_client().an_address()
+    }
+
+    pub fn a_block(&self) -> rpc::Block<TransactionId> {
+        self.test_client().a_block()
+    }
+
+    fn wait_until_ready(&mut self) -> Result<(), RegtestError> {
+        let probe = self.probe();
+        let started = Instant::now();
+
+        loop {
+            if let Some(status) = self.process.try_wait()? {
+                return Err(RegtestError::Exited(status));
+            }
+
+            match probe.get_block_count() {
+                Ok(_) => return Ok(()),
+                Err(e) if started.elapsed() > STARTUP_TIMEOUT => {
+                    return Err(RegtestError::NotReady(e))
+                }
+                Err(e) => trace!("bitcoind is not ready yet: {:?}", e),
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Since 0.21 bitcoind no longer creates a wallet on first start.
+    fn create_wallet(&self) -> Result<(), RegtestError> {
+        let version = self.client.node_version().map_err(RegtestError::NotReady)?;
+
+        if version < NodeVersion(210_000) {
+            return Ok(());
+        }
+
+        self.client
+            .call::<Value>("createwallet", &[json!("default")])
+            .map(|_| ())
+            .map_err(RegtestError::NotReady)
+    }
+
+    /// A client that doesn't wait for the node, which may not be running.
+    fn probe(&self) -> BitcoinCoreClient {
+        BitcoinCoreClient::new(&self.url, USERNAME, PASSWORD).with_retry_policy(RetryPolicy::none())
+    }
+
+    fn stop(&mut self) {
+        if let Ok(Some(_)) = self.process.try_wait() {
+            return;
+        }
+
+        if self.probe().call::<Value>("stop", &[]).is_ok() {
+            let stopping = Instant::now();
+
+            while stopping.elapsed() < SHUTDOWN_TIMEOUT {
+                if let Ok(Some(_)) = self.process.try_wait() {
+                    return;
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        }
+
+        warn!("Killing bitcoind in {}", self.datadir.display());
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+impl Drop for RegtestNode {
+    fn drop(&mut self) {
+        self.stop();
+
+        if let Err(e) = fs::remove_dir_all(&self.datadir) {
+            warn!("Unable to remove {}: {}", self.datadir.display(), e);
+        }
+    }
+}
+
+fn free_port() -> io::Result<u16> {
+    Ok(TcpListener::bind("127.0.0.1:0")?.local_addr()?.port())
+}
+
+impl From<io::Error> for RegtestError {
+    fn from(e: io::Error) -> Self {
+        RegtestError::Io(e)
+    }
+}
+
+impl fmt::Display for RegtestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RegtestError::Io(ref e) => write!(f, "unable to start bitcoind: {}", e),
+            RegtestError::Exited(status) => write!(f, "bitcoind exited on startup: {}", status),
+            RegtestError::NotReady(ref e) => write!(f, "bitcoind did not get ready: {}", e),
+        }
+    }
+}
+
+impl StdError for RegtestError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fails_if_bitcoind_is_missing() {
+        match RegtestNode::start_with_bitcoind("/nonexistent/bitcoind") {
+            Err(RegtestError::Io(ref e)) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("started a nonexistent bitcoind"),
+        }
+    }
+
+    #[test]
+    fn reports_exit_on_startup() {
+        // `false` exits immediately like bitcoind does on invalid options
+        match RegtestNode::start_with_bitcoind("false") {
+            Err(RegtestError::Exited(status)) => assert!(!status.success()),
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("false answered RPCs"),
+        }
+    }
+}
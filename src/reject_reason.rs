@@ -0,0 +1,212 @@
+use error_code::RpcErrorCode;
+use jsonrpc_client::RpcError;
+
+/// Why the node did not accept a transaction into its mempool.
+///
+/// Parsed from the message of the `RpcError` returned by `sendrawtransaction`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RejectReason {
+    /// The fee is below the minimum relay fee, the current mempool minimum fee or, for a
+    /// replacement, the fee of the replaced transactions.
+    InsufficientFee,
+    /// The fee exceeds the maximum fee the node is willing to pay.
+    AbsurdlyHighFee,
+    /// An input is already spent by a transaction in the mempool that cannot be replaced.
+    MempoolConflict,
+    AlreadyInMempool,
+    AlreadyInChain,
+    /// The lock time or relative lock time of the transaction has not been reached yet.
+    NonFinal,
+    Dust,
+    /// The transaction has too many unconfirmed ancestors or descendants.
+    TooLongMempoolChain,
+    MempoolFull,
+    /// An input does not exist or is already spent in the chain.
+    MissingInputs,
+    ScriptVerifyFailed {
+        /// Whether a consensus rule rather than a standardness rule was violated.
+        mandatory: bool,
+        detail: String,
+    },
+    /// A reason this crate does not know about.
+    Other(String),
+}
+
+impl RejectReason {
+    /// The reject reason of an error returned by `sendrawtransaction`, `None` if the error is not
+    /// about rejecting the transaction.
+    pub fn from_rpc_error(rpc_error: &RpcError) -> Option<Self> {
+        match RpcErrorCode::from(rpc_error.code) {
+            RpcErrorCode::VerifyError | RpcErrorCode::VerifyRejected => {
+                Some(Self::parse(&rpc_error.message))
+            }
+            RpcErrorCode::VerifyAlreadyInChain => Some(RejectReason::AlreadyInChain),
+            _ => None,
+        }
+    }
+
+    /// Parses a reject message like `min relay fee not met` or, as sent by nodes before 0.20,
+    /// `66: min relay fee not met`.
+    pub fn parse(message: &str) -> Self {
+        let reason = strip_reject_code(message);
+
+        if let Some(detail) = script_verify_detail(reason, "mandatory-script-verify-flag-failed") {
+            return RejectReason::ScriptVerifyFailed {
+                mandatory: true,
+                detail,
+            };
+        }
+        if let Some(detail) = script_verify_detail(reason, "non-mandatory-script-verify-flag") {
+            return RejectReason::ScriptVerifyFailed {
+                mandatory: false,
+                detail,
+            };
+        }
+
+        let starts_with = |prefixes: &[&str]| prefixes.iter().any(|p| reason.starts_with(p));
+
+        if starts_with(&[
+            "min relay fee not met",
+            "mempool min fee not met",
+            "insufficient fee",
+            "insufficient priority",
+        ]) {
+            RejectReason::InsufficientFee
+        } else if starts_with(&["absurdly-high-fee", "max-fee-exceeded"]) {
+            RejectReason::AbsurdlyHighFee
+        } else if starts_with(&["txn-mempool-conflict"]) {
+            RejectReason::MempoolConflict
+        } else if starts_with(&["txn-already-in-mempool", "txn-already-known"]) {
+            RejectReason::AlreadyInMempool
+        } else if starts_with(&["Transaction already in block chain", "txn-already-in-chain"]) {
+            RejectReason::AlreadyInChain
+        } else if starts_with(&["non-final", "bad-txns-nonfinal", "non-BIP68-final"]) {
+            RejectReason::NonFinal
+        } else if starts_with(&["dust"]) {
+            RejectReason::Dust
+        } else if starts_with(&["too-long-mempool-chain"]) {
+            RejectReason::TooLongMempoolChain
+        } else if starts_with(&["mempool full"]) {
+            RejectReason::MempoolFull
+        } else if starts_with(&[
+            "Missing inputs",
+            "missing-inputs",
+            "bad-txns-inputs-missingorspent",
+        ]) {
+            RejectReason::MissingInputs
+        } else {
+            RejectReason::Other(reason.to_string())
+        }
+    }
+}
+
+/// Removes the numeric reject code older nodes put in front of the reason.
+fn strip_reject_code(message: &str) -> &str {
+    let message = message.trim();
+
+    match message.find(": ") {
+        Some(index) if index > 0 && message[..index].chars().all(|c| c.is_ascii_digit()) => {
+            &message[index + 2..]
+        }
+        _ => message,
+    }
+}
+
+/// The detail in parentheses after a script verification failure.
+fn script_verify_detail(reason: &str, prefix: &str) -> Option<String> {
+    let detail = reason.strip_prefix(prefix)?.trim();
+    let detail = detail
+        .strip_prefix('(')
+        .and_then(|detail| detail.strip_suffix(')'))
+        .unwrap_or(detail);
+
+    Some(detail.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejected(message: &str) -> RpcError {
+        RpcError {
+            code: -26,
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_reject_messages_with_and_without_code() {
+        assert_eq!(
+            RejectReason::parse("66: min relay fee not met"),
+            RejectReason::InsufficientFee
+        );
+        assert_eq!(
+            RejectReason::parse("min relay fee not met, 100 < 141"),
+            RejectReason::InsufficientFee
+        );
+        assert_eq!(
+            RejectReason::parse("258: txn-mempool-conflict"),
+            RejectReason::MempoolConflict
+        );
+        assert_eq!(RejectReason::parse("64: non-final"), RejectReason::NonFinal);
+        assert_eq!(RejectReason::parse("dust"), RejectReason::Dust);
+        assert_eq!(
+            RejectReason::parse(
+                "too-long-mempool-chain, too many unconfirmed ancestors [limit: 25]"
+            ),
+            RejectReason::TooLongMempoolChain
+        );
+        assert_eq!(
+            RejectReason::parse("Missing inputs"),
+            RejectReason::MissingInputs
+        );
+        assert_eq!(
+            RejectReason::parse("64: scriptpubkey"),
+            RejectReason::Other(String::from("scriptpubkey"))
+        );
+    }
+
+    #[test]
+    fn parses_script_verify_failures() {
+        assert_eq!(
+            RejectReason::parse(
+                "16: mandatory-script-verify-flag-failed (Signature must be zero for failed CHECK(MULTI)SIG operation)"
+            ),
+            RejectReason::ScriptVerifyFailed {
+                mandatory: true,
+                detail: String::from(
+                    "Signature must be zero for failed CHECK(MULTI)SIG operation"
+                ),
+            }
+        );
+        assert_eq!(
+            RejectReason::parse("non-mandatory-script-verify-flag (Witness program hash mismatch)"),
+            RejectReason::ScriptVerifyFailed {
+                mandatory: false,
+                detail: String::from("Witness program hash mismatch"),
+            }
+        );
+    }
+
+    #[test]
+    fn only_parses_verify_errors() {
+        assert_eq!(
+            RejectReason::from_rpc_error(&rejected("txn-already-in-mempool")),
+            Some(RejectReason::AlreadyInMempool)
+        );
+        assert_eq!(
+            RejectReason::from_rpc_error(&RpcError {
+                code: -27,
+                message: String::from("Transaction already in block chain"),
+            }),
+            Some(RejectReason::AlreadyInChain)
+        );
+        assert_eq!(
+            RejectReason::from_rpc_error(&RpcError {
+                code: -22,
+                message: String::from("TX decode failed"),
+            }),
+            None
+        );
+    }
+}
@@ -0,0 +1,492 @@
+use error::Error;
+use error_code::RpcErrorCode;
+use jsonrpc_client::RpcError;
+use rand::{self, Rng};
+use reqwest::StatusCode;
+use std::{
+    error::Error as StdError,
+    io,
+    time::{Duration, Instant},
+};
+
+/// Methods that are safe to send again if a previous attempt may have been processed by the
+/// node: they only read state or their repetition has no further effect, e.g. `getnewaddress`.
+///
+/// `FailoverClient` only sends other methods to the next node after a timeout or a broken
+/// connection, since the node may have processed the failed attempt already. Sending a payment
+/// such as `send` or `sendrawtransaction` again could pay twice, repeating `generatetoaddress`
+/// mines more blocks than asked for. `RetryPolicy` doesn't need this, it only retries requests
+/// the node did not process.
+const IDEMPOTENT_METHODS: &[&str] = &[
+    "addmultisigaddress",
+    "analyzepsbt",
+    "combinepsbt",
+    "combinerawtransaction",
+    "converttopsbt",
+    "createmultisig",
+    "createpsbt",
+    "createrawtransaction",
+    "decodepsbt",
+    "decoderawtransaction",
+    "decodescript",
+    "deriveaddresses",
+    "dumpprivkey",
+    "estimatesmartfee",
+    "finalizepsbt",
+    "fundrawtransaction",
+    "getaddednodeinfo",
+    "getaddressesbylabel",
+    "getaddressinfo",
+    "getbalance",
+    "getbalances",
+    "getbestblockhash",
+    "getblock",
+    "getblockchaininfo",
+    "getblockcount",
+    "getblockfilter",
+    "getblockhash",
+    "getblockheader",
+    "getblockstats",
+    "getblocktemplate",
+    "getchaintips",
+    "getchaintxstats",
+    "getconnectioncount",
+    "getdeploymentinfo",
+    "getdescriptorinfo",
+    "getdifficulty",
+    "getindexinfo",
+    "getmemoryinfo",
+    "getmempoolancestors",
+    "getmempooldescendants",
+    "getmempoolentry",
+    "getmempoolinfo",
+    "getmininginfo",
+    "getnettotals",
+    "getnetworkhashps",
+    "getnetworkinfo",
+    "getnewaddress",
+    "getnodeaddresses",
+    "getpeerinfo",
+    "getrawchangeaddress",
+    "getrawmempool",
+    "getrawtransaction",
+    "getreceivedbyaddress",
+    "getreceivedbylabel",
+    "getrpcinfo",
+    "gettransaction",
+    "gettxout",
+    "gettxoutproof",
+    "gettxoutsetinfo",
+    "getunconfirmedbalance",
+    "getwalletinfo",
+    "help",
+    "joinpsbts",
+    "listaddressgroupings",
+    "listbanned",
+    "listdescriptors",
+    "listlabels",
+    "listlockunspent",
+    "listreceivedbyaddress",
+    "listreceivedbylabel",
+    "listsinceblock",
+    "listtransactions",
+    "listunspent",
+    "listwalletdir",
+    "listwallets",
+    "signmessage",
+    "signmessagewithprivkey",
+    "signrawtransactionwithkey",
+    "signrawtransactionwithwallet",
+    "testmempoolaccept",
+    "uptime",
+    "utxoupdatepsbt",
+    "validateaddress",
+    "verifychain",
+    "verifymessage",
+    "verifytxoutproof",
+    "walletcreatefundedpsbt",
+];
+
+/// Decides if and when a failed request is sent again.
+///
+/// The interval between two attempts grows exponentially from `initial_interval` up to
+/// `max_interval` and is randomized by `randomization_factor` to avoid synchronized retries
+/// of many clients. Requests are only retried if the node did not process them, i.e. it was
+/// warming up, refused the connection or had a full work queue, so retrying is safe for every
+/// method including payments such as `sendtoaddress` or `sendrawtransaction`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    initial_interval: Duration,
+    multiplier: f64,
+    max_interval: Duration,
+    randomization_factor: f64,
+    max_elapsed_time: Option<Duration>,
+    retry_on_warming_up: bool,
+    retry_on_connection_refused: bool,
+    retry_on_work_queue_exceeded: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::new()
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        RetryPolicy {
+            max_retries: 10,
+            initial_interval: Duration::from_millis(500),
+            multiplier: 1.5,
+            max_interval: Duration::from_secs(10),
+            randomization_factor: 0.2,
+            max_elapsed_time: Some(Duration::from_secs(60)),
+            retry_on_warming_up: true,
+            retry_on_connection_refused: true,
+            retry_on_work_queue_exceeded: true,
+        }
+    }
+
+    /// A policy that sends every request exactly once.
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_retries: 0,
+            ..RetryPolicy::new()
+        }
+    }
+
+    pub fn with_max_retries(self, max_retries: u32) -> Self {
+        RetryPolicy {
+            max_retries,
+            ..self
+        }
+    }
+    pub fn with_initial_interval(self, initial_interval: Duration) -> Self {
+        RetryPolicy {
+            initial_interval,
+            ..self
+        }
+    }
+    pub fn with_multiplier(self, multiplier: f64) -> Self {
+        RetryPolicy { multiplier, ..self }
+    }
+    pub fn with_max_interval(self, max_interval: Duration) -> Self {
+        RetryPolicy {
+            max_interval,
+            ..self
+        }
+    }
+    /// Every interval is randomly chosen from `interval * (1 ± randomization_factor)`.
+    pub fn with_randomization_factor(self, randomization_factor: f64) -> Self {
+        RetryPolicy {
+            randomization_factor,
+            ..self
+        }
+    }
+    /// No retry is scheduled past this time after the first attempt.
+    pub fn with_max_elapsed_time(self, max_elapsed_time: Option<Duration>) -> Self {
+        RetryPolicy {
+            max_elapsed_time,
+            ..self
+        }
+    }
+    /// Retry on RPC error -28, returned while bitcoind is starting up.
+    pub fn with_retry_on_warming_up(self, retry_on_warming_up: bool) -> Self {
+        RetryPolicy {
+            retry_on_warming_up,
+            ..self
+        }
+    }
+    pub fn with_retry_on_connection_refused(self, retry_on_connection_refused: bool) -> Self {
+        RetryPolicy {
+            retry_on_connection_refused,
+            ..self
+        }
+    }
+    /// Retry on HTTP 503, returned if more than `rpcworkqueue` requests are pending.
+    pub fn with_retry_on_work_queue_exceeded(self, retry_on_work_queue_exceeded: bool) -> Self {
+        RetryPolicy {
+            retry_on_work_queue_exceeded,
+            ..self
+        }
+    }
+
+    /// The interval before retry number `retry` (starting at 0), without randomization.
+    fn interval(&self, retry: u32) -> Duration {
+        let interval = duration_as_secs(self.initial_interval)
+            * self.multiplier.powi(retry.min(i32::MAX as u32) as i32);
+
+        if interval >= duration_as_secs(self.max_interval) {
+            self.max_interval
+        } else {
+            duration_from_secs(interval)
+        }
+    }
+
+    fn randomize(&self, interval: Duration) -> Duration {
+        let secs = duration_as_secs(interval);
+        let delta = secs * self.randomization_factor.min(1.0);
+
+        // An empty range can't be sampled, e.g. for a zero interval
+        if delta <= 0.0 {
+            return interval;
+        }
+
+        duration_from_secs(rand::thread_rng().gen_range(secs - delta, secs + delta))
+    }
+
+    /// Why to retry after `outcome`, only if the node certainly did not process the request.
+    fn reason<T, W>(&self, outcome: &Result<T, Error>, warming_up: W) -> Option<&'static str>
+    where
+        W: Fn(&T) -> bool,
+    {
+        match *outcome {
+            Ok(ref response) if self.retry_on_warming_up && warming_up(response) => {
+                Some("Bitcoind is still starting up")
+            }
+            Err(Error::HttpStatus(StatusCode::SERVICE_UNAVAILABLE))
+                if self.retry_on_work_queue_exceeded =>
+            {
+                Some("Bitcoind's work queue is full")
+            }
+            Err(Error::Transport(ref e))
+                if self.retry_on_connection_refused && is_connection_refused(e) =>
+            {
+                Some("Bitcoind refused the connection")
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Tracks the retries of a single request.
+pub(crate) struct Backoff {
+    policy: RetryPolicy,
+    retries: u32,
+    started_at: Instant,
+}
+
+impl Backoff {
+    pub(crate) fn new(policy: RetryPolicy) -> Self {
+        Backoff {
+            policy,
+            retries: 0,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Returns how long to wait before sending the request again, `None` if the outcome of the
+    /// last attempt is final.
+    pub(crate) fn next_delay<T, W>(
+        &mut self,
+        outcome: &Result<T, Error>,
+        warming_up: W,
+    ) -> Option<Duration>
+    where
+        W: Fn(&T) -> bool,
+    {
+        if self.retries >= self.policy.max_retries {
+            return None;
+        }
+
+        let reason = self.policy.reason(outcome, warming_up)?;
+        let delay = self.policy.randomize(self.policy.interval(self.retries));
+
+        if let Some(max_elapsed_time) = self.policy.max_elapsed_time {
+            if self.started_at.elapsed() + delay > max_elapsed_time {
+                return None;
+            }
+        }
+
+        self.retries += 1;
+        info!(
+            "{}. Request will be retried in {} milliseconds. ({}/{})",
+            reason,
+            delay.as_millis(),
+            self.retries,
+            self.policy.max_retries
+        );
+
+        Some(delay)
+    }
+}
+
+pub(crate) fn is_warming_up(rpc_error: &RpcError) -> bool {
+    RpcErrorCode::from(rpc_error.code) == RpcErrorCode::InWarmup
+}
+
+pub(crate) fn is_idempotent_method(method: &str) -> bool {
+    IDEMPOTENT_METHODS.contains(&method)
+}
+
+pub(crate) fn is_connection_refused(error: &(dyn StdError + 'static)) -> bool {
+    match error.downcast_ref::<io::Error>() {
+        Some(io_error) if io_error.kind() == io::ErrorKind::ConnectionRefused => true,
+        _ => match error.source() {
+            Some(source) => is_connection_refused(source),
+            None => false,
+        },
+    }
+}
+
+fn duration_as_secs(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1_000_000_000.0
+}
+
+fn duration_from_secs(secs: f64) -> Duration {
+    Duration::new(secs as u64, ((secs.fract()) * 1_000_000_000.0) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn warming_up() -> Result<Result<u32, RpcError>, Error> {
+        Ok(Err(RpcError {
+            code: -28,
+            message: String::from("Loading block index..."),
+        }))
+    }
+
+    fn is_warming_up_result(result: &Result<u32, RpcError>) -> bool {
+        match *result {
+            Err(ref rpc_error) => is_warming_up(rpc_error),
+            Ok(_) => false,
+        }
+    }
+
+    #[test]
+    fn interval_grows_exponentially_up_to_max_interval() {
+        let policy = RetryPolicy::new()
+            .with_initial_interval(Duration::from_millis(100))
+            .with_multiplier(2.0)
+            .with_max_interval(Duration::from_millis(500));
+
+        assert_eq!(policy.interval(0), Duration::from_millis(100));
+        assert_eq!(policy.interval(1), Duration::from_millis(200));
+        assert_eq!(policy.interval(2), Duration::from_millis(400));
+        assert_eq!(policy.interval(3), Duration::from_millis(500));
+        assert_eq!(policy.interval(100), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn randomized_interval_stays_within_factor() {
+        let policy = RetryPolicy::new().with_randomization_factor(0.5);
+
+        for _ in 0..100 {
+            let interval = policy.randomize(Duration::from_millis(1000));
+
+            assert!(interval >= Duration::from_millis(500));
+            assert!(interval <= Duration::from_millis(1500));
+        }
+    }
+
+    #[test]
+    fn retries_immediately_with_zero_interval() {
+        let policy = RetryPolicy::new().with_initial_interval(Duration::from_millis(0));
+        let mut backoff = Backoff::new(policy);
+
+        assert_eq!(
+            policy.randomize(Duration::from_millis(0)),
+            Duration::from_millis(0)
+        );
+        assert_eq!(
+            backoff.next_delay(&warming_up(), is_warming_up_result),
+            Some(Duration::from_millis(0))
+        );
+    }
+
+    #[test]
+    fn retries_while_warming_up_until_max_retries() {
+        let policy = RetryPolicy::new()
+            .with_max_retries(2)
+            .with_randomization_factor(0.0);
+        let mut backoff = Backoff::new(policy);
+
+        assert_eq!(
+            backoff.next_delay(&warming_up(), is_warming_up_result),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(
+            backoff.next_delay(&warming_up(), is_warming_up_result),
+            Some(Duration::from_millis(750))
+        );
+        assert_eq!(
+            backoff.next_delay(&warming_up(), is_warming_up_result),
+            None
+        );
+    }
+
+    #[test]
+    fn does_not_retry_final_outcomes() {
+        let mut backoff = Backoff::new(RetryPolicy::new());
+
+        assert_eq!(backoff.next_delay(&Ok(Ok(1)), is_warming_up_result), None);
+        assert_eq!(
+            backoff.next_delay(
+                &Ok(Err(RpcError {
+                    code: -8,
+                    message: String::from("Block height out of range"),
+                })),
+                is_warming_up_result
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn retries_requests_the_node_did_not_process() {
+        let mut backoff = Backoff::new(RetryPolicy::new().with_randomization_factor(0.0));
+
+        assert_eq!(
+            backoff.next_delay(
+                &Err::<u32, _>(Error::HttpStatus(StatusCode::SERVICE_UNAVAILABLE)),
+                |_| false
+            ),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(
+            backoff.next_delay(&Err::<u32, _>(Error::NoHealthyNode), |_| false),
+            None
+        );
+    }
+
+    #[test]
+    fn knows_idempotent_methods() {
+        for method in &[
+            "send",
+            "sendall",
+            "psbtbumpfee",
+            "generatetoaddress",
+            "newrpc",
+        ] {
+            assert!(!is_idempotent_method(method), "{}", method);
+        }
+        assert!(is_idempotent_method("getblockcount"));
+    }
+
+    #[test]
+    fn respects_max_elapsed_time() {
+        let policy = RetryPolicy::new()
+            .with_initial_interval(Duration::from_secs(2))
+            .with_max_elapsed_time(Some(Duration::from_secs(1)));
+        let mut backoff = Backoff::new(policy);
+
+        assert_eq!(
+            backoff.next_delay(&warming_up(), is_warming_up_result),
+            None
+        );
+    }
+
+    #[test]
+    fn detects_connection_refused() {
+        let error = io::Error::new(io::ErrorKind::ConnectionRefused, "refused");
+
+        assert!(is_connection_refused(&error));
+        assert!(!is_connection_refused(&io::Error::new(
+            io::ErrorKind::TimedOut,
+            "timed out"
+        )));
+    }
+}
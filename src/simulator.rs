@@ -0,0 +1,1256 @@
+use bitcoin::{
+    blockdata::{
+        script::{Builder, Instruction},
+        transaction::{OutPoint, SigHashType, Transaction as BitcoinTransaction, TxIn, TxOut},
+    },
+    consensus::encode::{deserialize, serialize},
+    util::{
+        bip143::SighashComponents,
+        key::{self, PublicKey},
+    },
+    Address, Network, Script,
+};
+use bitcoin_hashes::{hex::FromHex, sha256, sha256d, Hash};
+use error::Error;
+use error_code::RpcErrorCode;
+use jsonrpc_client::RpcError;
+use rpc;
+use secp256k1::{All, Message, Secp256k1, SecretKey, Signature};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+use std_hex;
+use BitcoinRpcApi;
+use BlockHash;
+use TransactionId;
+
+const COIN: u64 = 100_000_000;
+const COINBASE_MATURITY: u32 = 100;
+const HALVING_INTERVAL: u32 = 150;
+const GENESIS_HASH: &str = "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206";
+const GENESIS_TIME: u64 = 1_296_688_602;
+/// Seconds between two generated blocks.
+const BLOCK_INTERVAL: u64 = 600;
+/// The fee rate `send_to_address` pays, in satoshi per virtual byte.
+const FEE_RATE: u64 = 20;
+const DUST_LIMIT: u64 = 546;
+/// Lock times below are block heights, above Unix timestamps.
+const LOCKTIME_THRESHOLD: u32 = 500_000_000;
+/// Blocks whose median time a timestamp lock time is compared to, see BIP113.
+const MEDIAN_TIME_SPAN: usize = 11;
+
+/// A `BitcoinRpcApi` that simulates a regtest node with a wallet in memory.
+///
+/// Blocks are mined instantly by `generate`, coinbase outputs need 100 confirmations like on a
+/// real node. Transactions entering the mempool are checked for a lock time in the future,
+/// missing or already spent inputs, immature coinbase spends, outputs exceeding inputs and, for
+/// P2WPKH and P2PKH outputs, valid signatures.
+///
+/// Unlike bitcoind, the simulator does not evaluate other scripts: outputs such as P2WSH or
+/// P2SH are spent with any witness or script. Relative lock times (BIP68 sequences and
+/// `OP_CHECKSEQUENCEVERIFY`) are not enforced, and neither are fee or standardness policies.
+///
+/// Only the methods needed to fund, build, sign and broadcast transactions are simulated, all
+/// others panic. Signing only supports `SigHashType::All`.
+pub struct RegtestSimulator {
+    secp: Secp256k1<All>,
+    state: Mutex<State>,
+}
+
+struct State {
+    blocks: Vec<Block>,
+    transactions: HashMap<TransactionId, Entry>,
+    mempool: Vec<TransactionId>,
+    utxos: HashMap<OutPoint, Utxo>,
+    wallet: HashMap<Script, key::PrivateKey>,
+    /// The addresses of all scripts the simulator has seen as an address.
+    addresses: HashMap<Script, Address>,
+}
+
+struct Block {
+    hash: BlockHash,
+    time: u64,
+}
+
+struct Entry {
+    tx: BitcoinTransaction,
+    height: Option<u32>,
+    fee: u64,
+}
+
+#[derive(Clone)]
+struct Utxo {
+    output: TxOut,
+    height: Option<u32>,
+    coinbase: bool,
+}
+
+impl Default for RegtestSimulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RegtestSimulator {
+    /// A chain with only the genesis block and an empty wallet.
+    pub fn new() -> Self {
+        RegtestSimulator {
+            secp: Secp256k1::new(),
+            state: Mutex::new(State {
+                blocks: vec![Block {
+                    hash: BlockHash::from_hex(GENESIS_HASH).expect("valid genesis hash"),
+                    time: GENESIS_TIME,
+                }],
+                transactions: HashMap::new(),
+                mempool: Vec::new(),
+                utxos: HashMap::new(),
+                wallet: HashMap::new(),
+                addresses: HashMap::new(),
+            }),
+        }
+    }
+
+    /// The transactions waiting to be mined, in the order they were accepted.
+    pub fn mempool(&self) -> Vec<TransactionId> {
+        self.state.lock().unwrap().mempool.clone()
+    }
+
+    fn new_address(&self, state: &mut State) -> Address {
+        // Deterministic keys make failing tests reproducible
+        let seed = sha256::Hash::hash(&(state.wallet.len() as u64).to_le_bytes());
+        let private_key = key::PrivateKey {
+            compressed: true,
+            network: Network::Regtest,
+            key: SecretKey::from_slice(&seed[..]).expect("a hash is a valid secret key"),
+        };
+        let address = Address::p2wpkh(&private_key.public_key(&self.secp), Network::Regtest);
+
+        state.wallet.insert(address.script_pubkey(), private_key);
+        state
+            .addresses
+            .insert(address.script_pubkey(), address.clone());
+
+        address
+    }
+
+    fn mine(&self, state: &mut State, number_of_blocks: u32) -> Vec<BlockHash> {
+        (0..number_of_blocks)
+            .map(|_| {
+                let address = self.new_address(state);
+                state.mine_block(&address)
+            })
+            .collect()
+    }
+
+    fn accept(&self, state: &mut State, tx: BitcoinTransaction) -> Result<TransactionId, Error> {
+        let txid = tx.txid();
+
+        if let Some(entry) = state.transactions.get(&txid) {
+            return match entry.height {
+                Some(_) => Err(rpc_error(
+                    RpcErrorCode::VerifyAlreadyInChain,
+                    "Transaction already in block chain",
+                )),
+                None => Ok(txid),
+            };
+        }
+        if tx.is_coin_base() {
+            return Err(rejected("coinbase"));
+        }
+        if tx.input.is_empty() {
+            return Err(rejected("bad-txns-vin-empty"));
+        }
+        if tx.output.is_empty() {
+            return Err(rejected("bad-txns-vout-empty"));
+        }
+        if !state.is_final(&tx) {
+            return Err(rejected("non-final"));
+        }
+
+        let mut spent = HashSet::new();
+        let mut input_value = 0;
+
+        for (index, input) in tx.input.iter().enumerate() {
+            if !spent.insert(input.previous_output) {
+                return Err(rejected("bad-txns-inputs-duplicate"));
+            }
+
+            let utxo = match state.utxos.get(&input.previous_output) {
+                Some(utxo) => utxo,
+                None if state.is_spent_in_mempool(&input.previous_output) => {
+                    return Err(rejected("txn-mempool-conflict"));
+                }
+                None => {
+                    return Err(rpc_error(
+                        RpcErrorCode::VerifyError,
+                        "bad-txns-inputs-missingorspent",
+                    ));
+                }
+            };
+
+            if !state.is_mature(utxo) {
+                return Err(rejected("bad-txns-premature-spend-of-coinbase"));
+            }
+            if let Err(reason) = self.verify_input(&tx, index, &utxo.output) {
+                return Err(rejected(&format!(
+                    "mandatory-script-verify-flag-failed ({})",
+                    reason
+                )));
+            }
+
+            input_value += utxo.output.value;
+        }
+
+        let output_value = tx.output.iter().map(|output| output.value).sum::<u64>();
+        if output_value > input_value {
+            return Err(rejected("bad-txns-in-belowout"));
+        }
+
+        for input in &tx.input {
+            state.utxos.remove(&input.previous_output);
+        }
+        state.add_outputs(&tx, None, false);
+        state.mempool.push(txid);
+        state.transactions.insert(
+            txid,
+            Entry {
+                tx,
+                height: None,
+                fee: input_value - output_value,
+            },
+        );
+
+        Ok(txid)
+    }
+
+    fn verify_input(
+        &self,
+        tx: &BitcoinTransaction,
+        index: usize,
+        spent: &TxOut,
+    ) -> Result<(), &'static str> {
+        let script_pubkey = &spent.script_pubkey;
+
+        if script_pubkey.is_v0_p2wpkh() {
+            let witness = &tx.input[index].witness;
+            if witness.is_empty() {
+                return Err("Witness program was passed an empty witness");
+            }
+            if witness.len() != 2 {
+                return Err("Witness program hash mismatch");
+            }
+
+            let public_key = PublicKey::from_slice(&witness[1])
+                .map_err(|_| "Public key is neither compressed or uncompressed")?;
+            if Address::p2wpkh(&public_key, Network::Regtest).script_pubkey() != *script_pubkey {
+                return Err("Witness program hash mismatch");
+            }
+
+            let sighash = SighashComponents::new(tx).sighash_all(
+                &tx.input[index],
+                &Address::p2pkh(&public_key, Network::Regtest).script_pubkey(),
+                spent.value,
+            );
+
+            self.verify_signature(&sighash, &witness[0], &public_key)
+        } else if script_pubkey.is_p2pkh() {
+            let pushes = tx.input[index]
+                .script_sig
+                .iter(true)
+                .map(|instruction| match instruction {
+                    Instruction::PushBytes(bytes) => Ok(bytes),
+                    _ => Err("Only push operators allowed in signatures"),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if pushes.len() != 2 {
+                return Err("Operation not valid with the current stack size");
+            }
+
+            let public_key = PublicKey::from_slice(pushes[1])
+                .map_err(|_| "Public key is neither compressed or uncompressed")?;
+            if Address::p2pkh(&public_key, Network::Regtest).script_pubkey() != *script_pubkey {
+                return Err("Script failed an OP_EQUALVERIFY operation");
+            }
+
+            let sighash = tx.signature_hash(index, script_pubkey, SigHashType::All.as_u32());
+
+            self.verify_signature(&sighash, pushes[0], &public_key)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn verify_signature(
+        &self,
+        sighash: &sha256d::Hash,
+        signature: &[u8],
+        public_key: &PublicKey,
+    ) -> Result<(), &'static str> {
+        let (hash_type, signature) = match signature.split_last() {
+            Some((&hash_type, signature)) => (hash_type, signature),
+            None => {
+                return Err(
+                    "Script evaluated without error but finished with a false/empty top stack element",
+                );
+            }
+        };
+        if u32::from(hash_type) != SigHashType::All.as_u32() {
+            return Err("Signature hash type missing or not understood");
+        }
+
+        let signature =
+            Signature::from_der(signature).map_err(|_| "Non-canonical DER signature")?;
+        let message = Message::from_slice(&sighash[..]).expect("a sighash is 32 bytes");
+
+        self.secp
+            .verify(&message, &signature, &public_key.key)
+            .map_err(|_| "Signature must be zero for failed CHECK(MULTI)SIG operation")
+    }
+
+    /// Signs the P2WPKH or P2PKH input at `index`, given the output it spends.
+    fn sign_input(
+        &self,
+        tx: &mut BitcoinTransaction,
+        index: usize,
+        spent: &TxOut,
+        private_key: &key::PrivateKey,
+    ) {
+        let public_key = private_key.public_key(&self.secp);
+
+        if spent.script_pubkey.is_v0_p2wpkh() {
+            let sighash = SighashComponents::new(tx).sighash_all(
+                &tx.input[index],
+                &Address::p2pkh(&public_key, Network::Regtest).script_pubkey(),
+                spent.value,
+            );
+
+            tx.input[index].witness = vec![self.sign(&sighash, private_key), public_key.to_bytes()];
+        } else {
+            let sighash = tx.signature_hash(index, &spent.script_pubkey, SigHashType::All.as_u32());
+
+            tx.input[index].script_sig = Builder::new()
+                .push_slice(&self.sign(&sighash, private_key))
+                .push_key(&public_key)
+                .into_script();
+        }
+    }
+
+    fn sign(&self, sighash: &sha256d::Hash, private_key: &key::PrivateKey) -> Vec<u8> {
+        let message = Message::from_slice(&sighash[..]).expect("a sighash is 32 bytes");
+        let mut signature = self.secp.sign(&message, &private_key.key).serialize_der();
+        signature.push(SigHashType::All.as_u32() as u8);
+
+        signature
+    }
+
+    /// The key that can sign for `script_pubkey`, if it is a P2WPKH or P2PKH script.
+    fn find_key<'a>(
+        &self,
+        script_pubkey: &Script,
+        keys: &'a [key::PrivateKey],
+    ) -> Option<&'a key::PrivateKey> {
+        keys.iter().find(|private_key| {
+            let public_key = private_key.public_key(&self.secp);
+
+            Address::p2wpkh(&public_key, Network::Regtest).script_pubkey() == *script_pubkey
+                || Address::p2pkh(&public_key, Network::Regtest).script_pubkey() == *script_pubkey
+        })
+    }
+
+    fn decode(&self, state: &State, tx: &BitcoinTransaction) -> rpc::DecodedRawTransaction {
+        rpc::DecodedRawTransaction {
+            txid: tx.txid(),
+            hash: wtxid(tx).to_string(),
+            size: serialize(tx).len() as u32,
+            vsize: tx.get_weight().div_ceil(4) as u32,
+            version: tx.version,
+            locktime: tx.lock_time,
+            vin: tx
+                .input
+                .iter()
+                .map(|input| decode_input(tx, input))
+                .collect(),
+            vout: tx
+                .output
+                .iter()
+                .enumerate()
+                .map(|(n, output)| rpc::TransactionOutput {
+                    value: to_amount(output.value),
+                    n: n as u32,
+                    script_pub_key: state.decode_script(&output.script_pubkey),
+                })
+                .collect(),
+            fee: None,
+        }
+    }
+
+    fn transaction(
+        &self,
+        state: &State,
+        txid: &TransactionId,
+    ) -> Result<BitcoinTransaction, Error> {
+        state
+            .transactions
+            .get(txid)
+            .map(|entry| entry.tx.clone())
+            .ok_or_else(|| {
+                rpc_error(
+                    RpcErrorCode::InvalidAddressOrKey,
+                    "No such mempool or blockchain transaction",
+                )
+            })
+    }
+}
+
+impl State {
+    fn tip(&self) -> u32 {
+        self.blocks.len() as u32 - 1
+    }
+
+    /// The median time of the last blocks, which timestamp lock times must have passed.
+    fn median_time_past(&self) -> u64 {
+        let mut times = self
+            .blocks
+            .iter()
+            .rev()
+            .take(MEDIAN_TIME_SPAN)
+            .map(|block| block.time)
+            .collect::<Vec<_>>();
+        times.sort();
+
+        times[times.len() / 2]
+    }
+
+    /// Whether `tx` may be mined in the next block, like `IsFinalTx` of bitcoind.
+    ///
+    /// The lock time is ignored if all inputs have the final sequence number.
+    fn is_final(&self, tx: &BitcoinTransaction) -> bool {
+        let has_passed = if tx.lock_time < LOCKTIME_THRESHOLD {
+            tx.lock_time <= self.tip()
+        } else {
+            u64::from(tx.lock_time) < self.median_time_past()
+        };
+
+        tx.lock_time == 0
+            || has_passed
+            || tx.input.iter().all(|input| input.sequence == 0xffff_ffff)
+    }
+
+    fn confirmations(&self, height: Option<u32>) -> u32 {
+        height.map_or(0, |height| self.tip() - height + 1)
+    }
+
+    /// Whether `utxo` may be spent by a transaction entering the mempool now.
+    fn is_mature(&self, utxo: &Utxo) -> bool {
+        !utxo.coinbase || self.confirmations(utxo.height) >= COINBASE_MATURITY
+    }
+
+    /// The wallet waits for one more confirmation than consensus requires, so coins are only
+    /// spendable after 101 blocks like with bitcoind.
+    fn is_mature_for_wallet(&self, utxo: &Utxo) -> bool {
+        !utxo.coinbase || self.confirmations(utxo.height) > COINBASE_MATURITY
+    }
+
+    fn is_spent_in_mempool(&self, outpoint: &OutPoint) -> bool {
+        self.mempool.iter().any(|txid| {
+            self.transactions[txid]
+                .tx
+                .input
+                .iter()
+                .any(|input| input.previous_output == *outpoint)
+        })
+    }
+
+    fn add_outputs(&mut self, tx: &BitcoinTransaction, height: Option<u32>, coinbase: bool) {
+        let txid = tx.txid();
+
+        for (vout, output) in tx.output.iter().enumerate() {
+            self.utxos.insert(
+                OutPoint {
+                    txid,
+                    vout: vout as u32,
+                },
+                Utxo {
+                    output: output.clone(),
+                    height,
+                    coinbase,
+                },
+            );
+        }
+    }
+
+    /// Mines all transactions in the mempool, paying the subsidy and the fees to `address`.
+    fn mine_block(&mut self, address: &Address) -> BlockHash {
+        let height = self.tip() + 1;
+        let mempool = self.mempool.split_off(0);
+        let fees = mempool
+            .iter()
+            .map(|txid| self.transactions[txid].fee)
+            .sum::<u64>();
+        let coinbase = BitcoinTransaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                // BIP34 makes the coinbase of every block unique
+                script_sig: Builder::new().push_int(i64::from(height)).into_script(),
+                sequence: 0xffff_ffff,
+                witness: Vec::new(),
+            }],
+            output: vec![TxOut {
+                value: subsidy(height) + fees,
+                script_pubkey: address.script_pubkey(),
+            }],
+        };
+        let coinbase_txid = coinbase.txid();
+
+        self.add_outputs(&coinbase, Some(height), true);
+        self.transactions.insert(
+            coinbase_txid,
+            Entry {
+                tx: coinbase,
+                height: Some(height),
+                fee: 0,
+            },
+        );
+
+        let mut preimage = self.blocks[height as usize - 1].hash[..].to_vec();
+        preimage.extend_from_slice(&coinbase_txid[..]);
+
+        for txid in &mempool {
+            preimage.extend_from_slice(&txid[..]);
+
+            let entry = self
+                .transactions
+                .get_mut(txid)
+                .expect("mempool transactions are known");
+            entry.height = Some(height);
+
+            for vout in 0..entry.tx.output.len() as u32 {
+                let outpoint = OutPoint { txid: *txid, vout };
+
+                if let Some(utxo) = self.utxos.get_mut(&outpoint) {
+                    utxo.height = Some(height);
+                }
+            }
+        }
+
+        let hash = sha256d::Hash::hash(&preimage);
+        self.blocks.push(Block {
+            hash,
+            time: GENESIS_TIME + u64::from(height) * BLOCK_INTERVAL,
+        });
+
+        hash
+    }
+
+    /// The unspent outputs of the wallet that may be spent now, oldest first.
+    fn spendable_wallet_utxos(&self) -> Vec<(OutPoint, Utxo)> {
+        let mut utxos = self
+            .utxos
+            .iter()
+            .filter(|(_, utxo)| {
+                self.wallet.contains_key(&utxo.output.script_pubkey)
+                    && self.is_mature_for_wallet(utxo)
+            })
+            .map(|(outpoint, utxo)| (*outpoint, utxo.clone()))
+            .collect::<Vec<_>>();
+        utxos.sort_by_key(|(outpoint, utxo)| {
+            (
+                utxo.height.unwrap_or(u32::MAX),
+                outpoint.txid.to_string(),
+                outpoint.vout,
+            )
+        });
+
+        utxos
+    }
+
+    fn decode_script(&self, script: &Script) -> rpc::ScriptPubKey {
+        let script_type = if script.is_v0_p2wpkh() {
+            rpc::ScriptType::WitnessPubKeyHash
+        } else if script.is_v0_p2wsh() {
+            rpc::ScriptType::WitnessScriptHash
+        } else if script.is_p2pkh() {
+            rpc::ScriptType::PubKeyHash
+        } else if script.is_p2sh() {
+            rpc::ScriptType::ScriptHash
+        } else if script.is_p2pk() {
+            rpc::ScriptType::PubKey
+        } else if script.is_op_return() {
+            rpc::ScriptType::NullData
+        } else {
+            rpc::ScriptType::NonStandard
+        };
+        let address = self.addresses.get(script);
+
+        rpc::ScriptPubKey {
+            asm: script.asm(),
+            hex: script.clone(),
+            req_sigs: address.map(|_| 1),
+            script_type,
+            addresses: address.map(|address| vec![address.clone()]),
+            address: None,
+        }
+    }
+}
+
+impl BitcoinRpcApi for RegtestSimulator {
+    fn create_raw_transaction(
+        &self,
+        inputs: Vec<&rpc::NewTransactionInput>,
+        output: &rpc::NewTransactionOutput,
+    ) -> Result<rpc::SerializedRawTransaction, Error> {
+        let mut state = self.state.lock().unwrap();
+        let mut outputs = output.iter().collect::<Vec<_>>();
+        outputs.sort_by_key(|(address, _)| address.to_string());
+
+        let mut tx_outputs = Vec::new();
+        for (address, amount) in outputs {
+            if *amount < rpc::Amount::ZERO {
+                return Err(rpc_error(RpcErrorCode::TypeError, "Amount out of range"));
+            }
+
+            state
+                .addresses
+                .insert(address.script_pubkey(), address.clone());
+            tx_outputs.push(TxOut {
+                value: amount.as_sat() as u64,
+                script_pubkey: address.script_pubkey(),
+            });
+        }
+
+        let tx = BitcoinTransaction {
+            version: 2,
+            lock_time: 0,
+            input: inputs
+                .iter()
+                .map(|input| TxIn {
+                    previous_output: OutPoint {
+                        txid: input.txid,
+                        vout: input.vout,
+                    },
+                    script_sig: Script::new(),
+                    sequence: input.sequence.unwrap_or(0xffff_ffff),
+                    witness: Vec::new(),
+                })
+                .collect(),
+            output: tx_outputs,
+        };
+
+        Ok(rpc::SerializedRawTransaction::from(tx))
+    }
+
+    fn decode_rawtransaction(
+        &self,
+        tx: rpc::SerializedRawTransaction,
+    ) -> Result<rpc::DecodedRawTransaction, Error> {
+        let tx = decode_transaction(&tx)?;
+        let state = self.state.lock().unwrap();
+
+        Ok(self.decode(&state, &tx))
+    }
+
+    fn dump_privkey(&self, address: &Address) -> Result<rpc::PrivateKey, Error> {
+        let state = self.state.lock().unwrap();
+
+        match state.wallet.get(&address.script_pubkey()) {
+            Some(private_key) => Ok(rpc::PrivateKey::from(*private_key)),
+            None => Err(rpc_error(
+                RpcErrorCode::WalletError,
+                &format!("Private key for address {} is not known", address),
+            )),
+        }
+    }
+
+    fn generate(&self, number_of_blocks: u32) -> Result<Vec<BlockHash>, Error> {
+        let mut state = self.state.lock().unwrap();
+
+        Ok(self.mine(&mut state, number_of_blocks))
+    }
+
+    fn get_balance(&self) -> Result<rpc::Amount, Error> {
+        let state = self.state.lock().unwrap();
+        let balance = state
+            .spendable_wallet_utxos()
+            .iter()
+            .filter(|(_, utxo)| utxo.height.is_some())
+            .map(|(_, utxo)| utxo.output.value)
+            .sum::<u64>();
+
+        Ok(to_amount(balance))
+    }
+
+    fn get_best_block_hash(&self) -> Result<BlockHash, Error> {
+        let state = self.state.lock().unwrap();
+
+        Ok(state.blocks[state.tip() as usize].hash)
+    }
+
+    fn get_block_count(&self) -> Result<rpc::BlockHeight, Error> {
+        let state = self.state.lock().unwrap();
+
+        Ok(rpc::BlockHeight::new(state.tip()))
+    }
+
+    fn get_block_hash(&self, height: u32) -> Result<BlockHash, Error> {
+        let state = self.state.lock().unwrap();
+
+        state
+            .blocks
+            .get(height as usize)
+            .map(|block| block.hash)
+            .ok_or_else(|| rpc_error(RpcErrorCode::InvalidParameter, "Block height out of range"))
+    }
+
+    fn get_new_address(&self) -> Result<Address, Error> {
+        let mut state = self.state.lock().unwrap();
+
+        Ok(self.new_address(&mut state))
+    }
+
+    fn get_raw_transaction_serialized(
+        &self,
+        tx: &TransactionId,
+    ) -> Result<rpc::SerializedRawTransaction, Error> {
+        let state = self.state.lock().unwrap();
+
+        self.transaction(&state, tx)
+            .map(rpc::SerializedRawTransaction::from)
+    }
+
+    fn get_raw_transaction_verbose(
+        &self,
+        tx: &TransactionId,
+    ) -> Result<rpc::VerboseRawTransaction, Error> {
+        let state = self.state.lock().unwrap();
+        let transaction = self.transaction(&state, tx)?;
+
+        let height = state.transactions[tx].height;
+        let block = height.map(|height| &state.blocks[height as usize]);
+        let decoded = self.decode(&state, &transaction);
+
+        Ok(rpc::VerboseRawTransaction {
+            txid: decoded.txid,
+            hash: decoded.hash,
+            size: decoded.size,
+            vsize: decoded.vsize,
+            version: decoded.version,
+            locktime: decoded.locktime,
+            vin: decoded.vin,
+            vout: decoded.vout,
+            hex: rpc::SerializedRawTransaction::from(transaction),
+            blockhash: block.map(|block| block.hash),
+            confirmations: height.map(|height| state.confirmations(Some(height)) as i32),
+            time: block.map(|block| block.time),
+            blocktime: block.map(|block| block.time),
+        })
+    }
+
+    fn list_unspent(
+        &self,
+        min_confirmations: rpc::TxOutConfirmations,
+        max_confirmations: Option<u32>,
+        recipients: Option<Vec<Address>>,
+    ) -> Result<Vec<rpc::UnspentTransactionOutput>, Error> {
+        let min_confirmations = min_confirmations.min_confirmations().max(0) as u32;
+        let max_confirmations = max_confirmations.unwrap_or(9_999_999);
+        let state = self.state.lock().unwrap();
+
+        Ok(state
+            .spendable_wallet_utxos()
+            .into_iter()
+            .filter_map(|(outpoint, utxo)| {
+                let confirmations = state.confirmations(utxo.height);
+                let address = state.addresses[&utxo.output.script_pubkey].clone();
+
+                if confirmations < min_confirmations || confirmations > max_confirmations {
+                    return None;
+                }
+                if let Some(ref recipients) = recipients {
+                    if !recipients.contains(&address) {
+                        return None;
+                    }
+                }
+
+                Some(rpc::UnspentTransactionOutput {
+                    txid: outpoint.txid,
+                    vout: outpoint.vout,
+                    address: Some(address),
+                    account: None,
+                    script_pub_key: utxo.output.script_pubkey,
+                    redeem_script: None,
+                    amount: to_amount(utxo.output.value),
+                    confirmations: confirmations as i32,
+                    spendable: true,
+                    solvable: true,
+                    safe: Some(true),
+                })
+            })
+            .collect())
+    }
+
+    fn send_raw_transaction(
+        &self,
+        tx_data: rpc::SerializedRawTransaction,
+    ) -> Result<TransactionId, Error> {
+        let tx = decode_transaction(&tx_data)?;
+        let mut state = self.state.lock().unwrap();
+
+        self.accept(&mut state, tx)
+    }
+
+    fn send_to_address(
+        &self,
+        address: &Address,
+        amount: rpc::Amount,
+    ) -> Result<TransactionId, Error> {
+        if amount <= rpc::Amount::ZERO {
+            return Err(rpc_error(
+                RpcErrorCode::TypeError,
+                "Invalid amount for send",
+            ));
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let amount = amount.as_sat() as u64;
+        let mut selected = Vec::new();
+        let mut selected_value = 0;
+        let mut fee = 0;
+
+        // Oldest coins first, as long as they do not cover the amount and the fee
+        for (outpoint, utxo) in state.spendable_wallet_utxos() {
+            if utxo.height.is_none() {
+                continue;
+            }
+            if selected_value >= amount + fee {
+                break;
+            }
+
+            selected_value += utxo.output.value;
+            selected.push((outpoint, utxo));
+            fee = FEE_RATE * estimated_vsize(selected.len(), 2);
+        }
+
+        if selected_value < amount + fee {
+            return Err(rpc_error(
+                RpcErrorCode::WalletInsufficientFunds,
+                "Insufficient funds",
+            ));
+        }
+
+        state
+            .addresses
+            .insert(address.script_pubkey(), address.clone());
+
+        let mut outputs = vec![TxOut {
+            value: amount,
+            script_pubkey: address.script_pubkey(),
+        }];
+        let change = selected_value - amount - fee;
+        if change > DUST_LIMIT {
+            let change_address = self.new_address(&mut state);
+
+            outputs.push(TxOut {
+                value: change,
+                script_pubkey: change_address.script_pubkey(),
+            });
+        }
+
+        let mut tx = BitcoinTransaction {
+            version: 2,
+            lock_time: 0,
+            input: selected
+                .iter()
+                .map(|(outpoint, _)| TxIn {
+                    previous_output: *outpoint,
+                    script_sig: Script::new(),
+                    sequence: 0xffff_fffe,
+                    witness: Vec::new(),
+                })
+                .collect(),
+            output: outputs,
+        };
+
+        for (index, (_, utxo)) in selected.iter().enumerate() {
+            let private_key = state.wallet[&utxo.output.script_pubkey];
+
+            self.sign_input(&mut tx, index, &utxo.output, &private_key);
+        }
+
+        self.accept(&mut state, tx)
+    }
+
+    fn sign_raw_transaction_with_key(
+        &self,
+        tx: &rpc::SerializedRawTransaction,
+        private_keys: Option<Vec<&rpc::PrivateKey>>,
+        dependencies: Option<Vec<&rpc::TransactionOutputDetail>>,
+        signature_hash_type: Option<rpc::SigHashType>,
+    ) -> Result<rpc::SigningResult, Error> {
+        match signature_hash_type {
+            None | Some(rpc::SigHashType::All) => {}
+            Some(_) => {
+                return Err(rpc_error(
+                    RpcErrorCode::InvalidParameter,
+                    "The simulator only signs with sighash type ALL",
+                ));
+            }
+        }
+
+        let mut tx = decode_transaction(tx)?;
+        let keys = private_keys
+            .unwrap_or_default()
+            .into_iter()
+            .map(key::PrivateKey::from)
+            .collect::<Vec<_>>();
+        let dependencies = dependencies.unwrap_or_default();
+        let state = self.state.lock().unwrap();
+        let mut errors = Vec::new();
+
+        for index in 0..tx.input.len() {
+            let outpoint = tx.input[index].previous_output;
+            // Only P2PKH inputs can be signed without knowing the amount they spend
+            let spent = state
+                .utxos
+                .get(&outpoint)
+                .map(|utxo| utxo.output.clone())
+                .or_else(|| {
+                    dependencies
+                        .iter()
+                        .find(|dependency| {
+                            dependency.txid == outpoint.txid && dependency.vout == outpoint.vout
+                        })
+                        .filter(|dependency| dependency.script_pub_key.is_p2pkh())
+                        .map(|dependency| TxOut {
+                            value: 0,
+                            script_pubkey: dependency.script_pub_key.clone(),
+                        })
+                });
+
+            let error = match spent {
+                Some(spent) => match self.find_key(&spent.script_pubkey, &keys) {
+                    Some(private_key) => {
+                        self.sign_input(&mut tx, index, &spent, private_key);
+                        continue;
+                    }
+                    None => "Unable to sign input, invalid stack size (possibly missing key)",
+                },
+                None => "Input not found or already spent",
+            };
+
+            errors.push(rpc::SigningError {
+                txid: outpoint.txid,
+                vout: outpoint.vout,
+                script_sig: std_hex::encode(tx.input[index].script_sig.as_bytes()),
+                sequence: tx.input[index].sequence,
+                error: error.to_string(),
+            });
+        }
+
+        Ok(rpc::SigningResult {
+            hex: rpc::SerializedRawTransaction::from(tx).0,
+            complete: errors.is_empty(),
+            errors: if errors.is_empty() {
+                None
+            } else {
+                Some(errors)
+            },
+        })
+    }
+}
+
+fn decode_input(tx: &BitcoinTransaction, input: &TxIn) -> rpc::TransactionInput {
+    if tx.is_coin_base() {
+        return rpc::TransactionInput::Coinbase {
+            coinbase: std_hex::encode(input.script_sig.as_bytes()),
+            witness: input.witness.iter().map(std_hex::encode).collect(),
+            sequence: u64::from(input.sequence),
+        };
+    }
+
+    rpc::TransactionInput::Regular {
+        txid: input.previous_output.txid,
+        vout: input.previous_output.vout,
+        script_sig: rpc::ScriptSig {
+            asm: input.script_sig.asm(),
+            hex: input.script_sig.clone(),
+        },
+        witness: input.witness.iter().map(std_hex::encode).collect(),
+        sequence: u64::from(input.sequence),
+    }
+}
+
+fn decode_transaction(tx: &rpc::SerializedRawTransaction) -> Result<BitcoinTransaction, Error> {
+    Vec::<u8>::from_hex(&tx.0)
+        .ok()
+        .and_then(|bytes| deserialize(&bytes).ok())
+        .ok_or_else(|| rpc_error(RpcErrorCode::DeserializationError, "TX decode failed"))
+}
+
+fn wtxid(tx: &BitcoinTransaction) -> sha256d::Hash {
+    sha256d::Hash::hash(&serialize(tx))
+}
+
+/// The block subsidy of regtest, which halves every 150 blocks.
+fn subsidy(height: u32) -> u64 {
+    match height / HALVING_INTERVAL {
+        halvings if halvings < 64 => (50 * COIN) >> halvings,
+        _ => 0,
+    }
+}
+
+/// The virtual size of a transaction spending P2WPKH inputs to P2WPKH outputs.
+fn estimated_vsize(inputs: usize, outputs: usize) -> u64 {
+    11 + 68 * inputs as u64 + 31 * outputs as u64
+}
+
+fn to_amount(sat: u64) -> rpc::Amount {
+    rpc::Amount::from_sat(sat as i64)
+}
+
+fn rpc_error(code: RpcErrorCode, message: &str) -> Error {
+    Error::Rpc(RpcError {
+        code: code.code(),
+        message: message.to_string(),
+    })
+}
+
+fn rejected(reason: &str) -> Error {
+    rpc_error(RpcErrorCode::VerifyRejected, reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reject_reason::RejectReason;
+    use std::collections::HashMap;
+
+    fn btc(amount: &str) -> rpc::Amount {
+        amount.parse().unwrap()
+    }
+
+    /// A simulator whose wallet owns a single mature coinbase output of 50 BTC.
+    fn funded_simulator() -> (RegtestSimulator, rpc::UnspentTransactionOutput) {
+        let simulator = RegtestSimulator::new();
+        simulator.generate(101).unwrap();
+
+        let mut utxos = simulator
+            .list_unspent(rpc::TxOutConfirmations::AtLeast(1), None, None)
+            .unwrap();
+        assert_eq!(utxos.len(), 1);
+
+        (simulator, utxos.remove(0))
+    }
+
+    fn spend(
+        simulator: &RegtestSimulator,
+        utxo: &rpc::UnspentTransactionOutput,
+        amount: rpc::Amount,
+    ) -> rpc::SerializedRawTransaction {
+        let private_key = simulator
+            .dump_privkey(utxo.address.as_ref().unwrap())
+            .unwrap();
+        let mut output = HashMap::new();
+        output.insert(simulator.get_new_address().unwrap(), amount);
+
+        let tx = simulator
+            .create_raw_transaction(vec![&rpc::NewTransactionInput::from_utxo(utxo)], &output)
+            .unwrap();
+        let signed = simulator
+            .sign_raw_transaction_with_key(&tx, Some(vec![&private_key]), None, None)
+            .unwrap();
+        assert!(signed.complete);
+
+        rpc::SerializedRawTransaction(signed.hex)
+    }
+
+    fn reject_reason(result: Result<TransactionId, Error>) -> RejectReason {
+        result.unwrap_err().reject_reason().unwrap()
+    }
+
+    #[test]
+    fn coinbase_outputs_mature_after_100_blocks() {
+        let simulator = RegtestSimulator::new();
+
+        simulator.generate(100).unwrap();
+        assert_eq!(simulator.get_balance().unwrap(), rpc::Amount::ZERO);
+
+        simulator.generate(1).unwrap();
+        assert_eq!(simulator.get_balance().unwrap(), btc("50"));
+        assert_eq!(
+            simulator.get_block_count().unwrap(),
+            rpc::BlockHeight::new(101)
+        );
+        assert_eq!(
+            simulator.get_block_hash(101).unwrap(),
+            simulator.get_best_block_hash().unwrap()
+        );
+    }
+
+    #[test]
+    fn confirms_sent_transactions() {
+        let (simulator, _) = funded_simulator();
+        let address = simulator.get_new_address().unwrap();
+
+        let txid = simulator.send_to_address(&address, btc("1.5")).unwrap();
+        assert_eq!(simulator.mempool(), vec![txid]);
+        let unconfirmed = simulator.get_raw_transaction_verbose(&txid).unwrap();
+        assert_eq!(unconfirmed.blockhash, None);
+        assert_eq!(unconfirmed.confirmations, None);
+
+        let blocks = simulator.generate(1).unwrap();
+        let tx = simulator.get_raw_transaction_verbose(&txid).unwrap();
+
+        assert!(simulator.mempool().is_empty());
+        assert_eq!(tx.blockhash, Some(blocks[0]));
+        assert_eq!(tx.confirmations, Some(1));
+        assert!(tx.vout.iter().any(|output| output.value == btc("1.5")
+            && output.script_pub_key.addresses == Some(vec![address.clone()])));
+    }
+
+    #[test]
+    fn accepts_signed_raw_transactions() {
+        let (simulator, utxo) = funded_simulator();
+
+        let txid = simulator
+            .send_raw_transaction(spend(&simulator, &utxo, btc("49.999")))
+            .unwrap();
+        simulator.generate(1).unwrap();
+
+        let unspent = simulator
+            .list_unspent(rpc::TxOutConfirmations::AtLeast(1), None, None)
+            .unwrap();
+        assert!(unspent
+            .iter()
+            .any(|output| output.txid == txid && output.amount == btc("49.999")));
+        assert!(!unspent.iter().any(|output| output.txid == utxo.txid));
+    }
+
+    #[test]
+    fn rejects_double_spends() {
+        let (simulator, utxo) = funded_simulator();
+        let first_spend = spend(&simulator, &utxo, btc("49.0"));
+        let double_spend = spend(&simulator, &utxo, btc("48.0"));
+
+        simulator.send_raw_transaction(first_spend).unwrap();
+        assert_eq!(
+            reject_reason(simulator.send_raw_transaction(double_spend.clone())),
+            RejectReason::MempoolConflict
+        );
+
+        simulator.generate(1).unwrap();
+        assert_eq!(
+            reject_reason(simulator.send_raw_transaction(double_spend)),
+            RejectReason::MissingInputs
+        );
+    }
+
+    #[test]
+    fn rejects_outputs_exceeding_inputs() {
+        let (simulator, utxo) = funded_simulator();
+
+        assert_eq!(
+            reject_reason(simulator.send_raw_transaction(spend(&simulator, &utxo, btc("51.0")))),
+            RejectReason::Other(String::from("bad-txns-in-belowout"))
+        );
+    }
+
+    #[test]
+    fn rejects_immature_coinbase_spends() {
+        let simulator = RegtestSimulator::new();
+        simulator.generate(1).unwrap();
+
+        let utxo = {
+            let state = simulator.state.lock().unwrap();
+            let (txid, entry) = state
+                .transactions
+                .iter()
+                .find(|(_, entry)| entry.tx.is_coin_base())
+                .unwrap();
+            let script_pubkey = entry.tx.output[0].script_pubkey.clone();
+
+            rpc::UnspentTransactionOutput {
+                txid: *txid,
+                vout: 0,
+                address: Some(state.addresses[&script_pubkey].clone()),
+                account: None,
+                script_pub_key: script_pubkey,
+                redeem_script: None,
+                amount: btc("50"),
+                confirmations: 1,
+                spendable: true,
+                solvable: true,
+                safe: Some(true),
+            }
+        };
+
+        assert_eq!(
+            reject_reason(simulator.send_raw_transaction(spend(&simulator, &utxo, btc("49.0")))),
+            RejectReason::Other(String::from("bad-txns-premature-spend-of-coinbase"))
+        );
+    }
+
+    #[test]
+    fn rejects_transactions_locked_until_later() {
+        let (simulator, utxo) = funded_simulator();
+        let tx = decode_transaction(&spend(&simulator, &utxo, btc("49.0"))).unwrap();
+        let locked = |lock_time| {
+            let mut tx = tx.clone();
+            tx.lock_time = lock_time;
+            tx.input[0].sequence = 0xffff_fffe;
+
+            simulator.send_raw_transaction(tx.into())
+        };
+
+        assert_eq!(reject_reason(locked(102)), RejectReason::NonFinal);
+        assert_eq!(
+            reject_reason(locked(GENESIS_TIME as u32 + 102 * BLOCK_INTERVAL as u32)),
+            RejectReason::NonFinal
+        );
+        // Past lock times pass, the signature committing to the original one doesn't
+        match reject_reason(locked(101)) {
+            RejectReason::ScriptVerifyFailed { .. } => {}
+            reason => panic!(
+                "expected a script verification failure but got {:?}",
+                reason
+            ),
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_signatures() {
+        let (simulator, utxo) = funded_simulator();
+        let mut tx = decode_transaction(&spend(&simulator, &utxo, btc("49.0"))).unwrap();
+
+        tx.output[0].value -= 1;
+
+        match reject_reason(simulator.send_raw_transaction(tx.into())) {
+            RejectReason::ScriptVerifyFailed {
+                mandatory: true, ..
+            } => {}
+            reason => panic!(
+                "expected a script verification failure but got {:?}",
+                reason
+            ),
+        }
+    }
+
+    #[test]
+    fn reports_inputs_it_cannot_sign() {
+        let (simulator, utxo) = funded_simulator();
+        let mut output = HashMap::new();
+        output.insert(simulator.get_new_address().unwrap(), btc("49"));
+
+        let tx = simulator
+            .create_raw_transaction(vec![&rpc::NewTransactionInput::from_utxo(&utxo)], &output)
+            .unwrap();
+        let signed = simulator
+            .sign_raw_transaction_with_key(&tx, None, None, None)
+            .unwrap();
+
+        assert!(!signed.complete);
+        assert_eq!(signed.errors.unwrap()[0].txid, utxo.txid);
+    }
+}
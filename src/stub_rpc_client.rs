@@ -1,11 +1,404 @@
+use bitcoin::{Address, Script};
 use bitcoin_rpc_api::BitcoinRpcApi;
+use error::Error;
+use jsonrpc_client::RpcError;
+use rpc;
+use serde::Serialize;
+use serde_json::{self, Value};
+use std::{any::Any, collections::HashMap, sync::Mutex};
+use types::address::AddressInfoResult;
+use BlockHash;
+use TransactionId;
 
-pub struct BitcoinStubClient {}
+type Handler = Box<dyn FnMut(&[Value]) -> Result<Box<dyn Any + Send>, Error> + Send>;
+
+/// A `BitcoinRpcApi` for tests that answers with canned responses.
+///
+/// Responses are registered per RPC method, e.g. `getblockcount`, and every call is recorded
+/// with its arguments. Calling a method without a registered response panics. Methods that send
+/// the same RPC, like `get_block` and `get_block_verbose`, share the registered response.
+///
+/// ```
+/// # use bitcoin_rpc_client::{BitcoinRpcApi, BitcoinStubClient, RpcError, rpc::BlockHeight};
+/// let stub = BitcoinStubClient::new()
+///     .with_results("getblockcount", vec![Ok(BlockHeight::new(100)), Ok(BlockHeight::new(101))])
+///     .with_error("getbestblockhash", RpcError { code: -28, message: "Loading".into() });
+///
+/// assert_eq!(stub.get_block_count().unwrap(), BlockHeight::new(100));
+/// assert_eq!(stub.get_block_count().unwrap(), BlockHeight::new(101));
+/// assert!(stub.get_best_block_hash().is_err());
+/// assert_eq!(stub.call_count("getblockcount"), 2);
+/// ```
+#[derive(Default)]
+pub struct BitcoinStubClient {
+    handlers: Mutex<HashMap<String, Handler>>,
+    calls: Mutex<Vec<StubCall>>,
+}
+
+/// A call made to a `BitcoinStubClient`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StubCall {
+    /// The name of the RPC, e.g. `getblockcount`.
+    pub method: String,
+    /// The arguments of the call, serialized to JSON.
+    pub params: Vec<Value>,
+}
 
 impl BitcoinStubClient {
     pub fn new() -> Self {
-        Self {}
+        Self::default()
+    }
+
+    /// Answers every call to `method` with the result of `handler`, which gets the arguments
+    /// of the call.
+    pub fn with_handler<T, F>(self, method: &str, mut handler: F) -> Self
+    where
+        T: Send + 'static,
+        F: FnMut(&[Value]) -> Result<T, Error> + Send + 'static,
+    {
+        self.with(
+            method,
+            Box::new(move |params| handler(params).map(|result| Box::new(result) as _)),
+        )
+    }
+
+    /// Answers every call to `method` with `result`.
+    pub fn with_result<T: Clone + Send + 'static>(self, method: &str, result: T) -> Self {
+        self.with_handler(method, move |_| Ok(result.clone()))
+    }
+
+    /// Answers the calls to `method` with `results`, one after the other. Calling `method` more
+    /// often panics.
+    pub fn with_results<T: Send + 'static>(
+        self,
+        method: &str,
+        results: Vec<Result<T, Error>>,
+    ) -> Self {
+        let name = method.to_string();
+        let mut results = results.into_iter();
+
+        self.with_handler(method, move |_| match results.next() {
+            Some(result) => result,
+            None => panic!("{} was called more often than stubbed", name),
+        })
+    }
+
+    /// Fails every call to `method` with `rpc_error`.
+    pub fn with_error(self, method: &str, rpc_error: RpcError) -> Self {
+        self.with(
+            method,
+            Box::new(move |_| {
+                Err(Error::Rpc(RpcError {
+                    code: rpc_error.code,
+                    message: rpc_error.message.clone(),
+                }))
+            }),
+        )
+    }
+
+    fn with(mut self, method: &str, handler: Handler) -> Self {
+        self.handlers
+            .get_mut()
+            .unwrap()
+            .insert(method.to_string(), handler);
+        self
+    }
+
+    /// All calls made so far, in order.
+    pub fn calls(&self) -> Vec<StubCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// The RPC methods called so far, in order.
+    pub fn called_methods(&self) -> Vec<String> {
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|call| call.method.clone())
+            .collect()
+    }
+
+    /// How often `method` was called so far.
+    pub fn call_count(&self, method: &str) -> usize {
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|call| call.method == method)
+            .count()
+    }
+
+    fn respond<T: 'static>(&self, method: &str, params: Vec<Value>) -> Result<T, Error> {
+        self.calls.lock().unwrap().push(StubCall {
+            method: method.to_string(),
+            params: params.clone(),
+        });
+
+        let mut handlers = self.handlers.lock().unwrap();
+        let handler = match handlers.get_mut(method) {
+            Some(handler) => handler,
+            None => panic!("{} was called but not stubbed", method),
+        };
+
+        handler(&params).map(|result| match result.downcast::<T>() {
+            Ok(result) => *result,
+            Err(_) => panic!("{} was stubbed with a result of the wrong type", method),
+        })
+    }
+}
+
+fn param<T: Serialize>(value: T) -> Value {
+    serde_json::to_value(value).expect("parameters of RPCs serialize to JSON")
+}
+
+impl BitcoinRpcApi for BitcoinStubClient {
+    fn add_multisig_address(
+        &self,
+        number_of_required_signatures: u32,
+        participants: Vec<&Address>,
+    ) -> Result<rpc::MultiSigAddress, Error> {
+        self.respond(
+            "addmultisigaddress",
+            vec![param(number_of_required_signatures), param(participants)],
+        )
+    }
+
+    fn create_raw_transaction(
+        &self,
+        inputs: Vec<&rpc::NewTransactionInput>,
+        output: &rpc::NewTransactionOutput,
+    ) -> Result<rpc::SerializedRawTransaction, Error> {
+        self.respond("createrawtransaction", vec![param(inputs), param(output)])
+    }
+
+    fn decode_rawtransaction(
+        &self,
+        tx: rpc::SerializedRawTransaction,
+    ) -> Result<rpc::DecodedRawTransaction, Error> {
+        self.respond("decoderawtransaction", vec![param(tx)])
+    }
+
+    fn decode_script(&self, script: Script) -> Result<rpc::DecodedScript, Error> {
+        self.respond("decodescript", vec![param(script)])
+    }
+
+    fn dump_privkey(&self, address: &Address) -> Result<rpc::PrivateKey, Error> {
+        self.respond("dumpprivkey", vec![param(address)])
+    }
+
+    fn fund_raw_transaction(
+        &self,
+        tx: &rpc::SerializedRawTransaction,
+        options: &rpc::FundingOptions,
+    ) -> Result<rpc::FundingResult, Error> {
+        self.respond("fundrawtransaction", vec![param(tx), param(options)])
+    }
+
+    fn generate(&self, number_of_blocks: u32) -> Result<Vec<BlockHash>, Error> {
+        self.respond("generate", vec![param(number_of_blocks)])
+    }
+
+    fn get_address_info(&self, address: &Address) -> Result<AddressInfoResult, Error> {
+        self.respond("getaddressinfo", vec![param(address)])
+    }
+
+    fn get_balance(&self) -> Result<rpc::Amount, Error> {
+        self.respond("getbalance", vec![])
+    }
+
+    fn get_best_block_hash(&self) -> Result<BlockHash, Error> {
+        self.respond("getbestblockhash", vec![])
+    }
+
+    fn get_block(&self, header_hash: &BlockHash) -> Result<rpc::Block<TransactionId>, Error> {
+        self.respond("getblock", vec![param(header_hash)])
+    }
+
+    fn get_block_verbose(
+        &self,
+        header_hash: &BlockHash,
+    ) -> Result<rpc::Block<rpc::DecodedRawTransaction>, Error> {
+        self.respond("getblock", vec![param(header_hash), param(2)])
+    }
+
+    fn get_blockchain_info(&self) -> Result<rpc::BlockchainInfo, Error> {
+        self.respond("getblockchaininfo", vec![])
+    }
+
+    fn get_block_count(&self) -> Result<rpc::BlockHeight, Error> {
+        self.respond("getblockcount", vec![])
+    }
+
+    fn get_block_hash(&self, height: u32) -> Result<BlockHash, Error> {
+        self.respond("getblockhash", vec![param(height)])
+    }
+
+    fn get_new_address(&self) -> Result<Address, Error> {
+        self.respond("getnewaddress", vec![])
+    }
+
+    fn get_raw_transaction_serialized(
+        &self,
+        tx: &TransactionId,
+    ) -> Result<rpc::SerializedRawTransaction, Error> {
+        self.respond("getrawtransaction", vec![param(tx), param(false)])
+    }
+
+    fn get_raw_transaction_verbose(
+        &self,
+        tx: &TransactionId,
+    ) -> Result<rpc::VerboseRawTransaction, Error> {
+        self.respond("getrawtransaction", vec![param(tx), param(true)])
+    }
+
+    fn list_unspent(
+        &self,
+        min_confirmations: rpc::TxOutConfirmations,
+        max_confirmations: Option<u32>,
+        recipients: Option<Vec<Address>>,
+    ) -> Result<Vec<rpc::UnspentTransactionOutput>, Error> {
+        self.respond(
+            "listunspent",
+            vec![
+                param(min_confirmations),
+                param(max_confirmations),
+                param(recipients),
+            ],
+        )
+    }
+
+    fn send_raw_transaction(
+        &self,
+        tx_data: rpc::SerializedRawTransaction,
+    ) -> Result<TransactionId, Error> {
+        self.respond("sendrawtransaction", vec![param(tx_data)])
+    }
+
+    fn send_to_address(
+        &self,
+        address: &Address,
+        amount: rpc::Amount,
+    ) -> Result<TransactionId, Error> {
+        self.respond("sendtoaddress", vec![param(address), param(amount)])
+    }
+
+    fn sign_raw_transaction_with_key(
+        &self,
+        tx: &rpc::SerializedRawTransaction,
+        private_keys: Option<Vec<&rpc::PrivateKey>>,
+        dependencies: Option<Vec<&rpc::TransactionOutputDetail>>,
+        signature_hash_type: Option<rpc::SigHashType>,
+    ) -> Result<rpc::SigningResult, Error> {
+        self.respond(
+            "signrawtransactionwithkey",
+            vec![
+                param(tx),
+                param(private_keys),
+                param(dependencies),
+                param(signature_hash_type),
+            ],
+        )
+    }
+
+    fn validate_address(&self, address: &Address) -> Result<rpc::AddressValidationResult, Error> {
+        self.respond("validateaddress", vec![param(address)])
     }
 }
 
-impl BitcoinRpcApi for BitcoinStubClient {}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use error_code::RpcErrorCode;
+    use std::str::FromStr;
+
+    #[test]
+    fn answers_with_sequence_and_records_calls() {
+        let stub = BitcoinStubClient::new()
+            .with_results(
+                "getblockcount",
+                vec![Ok(rpc::BlockHeight::new(1)), Ok(rpc::BlockHeight::new(2))],
+            )
+            .with_handler("getblockhash", |params| {
+                assert_eq!(params, &[Value::from(2)]);
+                Ok(BlockHash::default())
+            });
+
+        assert_eq!(stub.get_block_count().unwrap(), rpc::BlockHeight::new(1));
+        assert_eq!(stub.get_block_count().unwrap(), rpc::BlockHeight::new(2));
+        assert_eq!(stub.get_block_hash(2).unwrap(), BlockHash::default());
+
+        assert_eq!(stub.call_count("getblockcount"), 2);
+        assert_eq!(
+            stub.called_methods(),
+            vec!["getblockcount", "getblockcount", "getblockhash"]
+        );
+    }
+
+    #[test]
+    fn fails_with_stubbed_rpc_error() {
+        let stub = BitcoinStubClient::new().with_error(
+            "sendrawtransaction",
+            RpcError {
+                code: -26,
+                message: String::from("min relay fee not met"),
+            },
+        );
+
+        let error = stub
+            .send_raw_transaction(rpc::SerializedRawTransaction(String::from("00")))
+            .unwrap_err();
+
+        assert_eq!(error.rpc_error_code(), Some(RpcErrorCode::VerifyRejected));
+        assert_eq!(
+            stub.calls(),
+            vec![StubCall {
+                method: String::from("sendrawtransaction"),
+                params: vec![Value::from("00")],
+            }]
+        );
+    }
+
+    #[test]
+    fn records_arguments_as_json() {
+        let address = Address::from_str("2N8hwP1WmJrFF5QWABn38y63uYLhnJYJYTF").unwrap();
+        let stub = BitcoinStubClient::new().with_error(
+            "validateaddress",
+            RpcError {
+                code: -5,
+                message: String::from("Invalid address"),
+            },
+        );
+
+        let _ = stub.validate_address(&address);
+
+        assert_eq!(
+            stub.calls()[0].params,
+            vec![Value::from("2N8hwP1WmJrFF5QWABn38y63uYLhnJYJYTF")]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "getblockcount was stubbed with a result of the wrong type")]
+    fn panics_on_result_of_wrong_type() {
+        let _ = BitcoinStubClient::new()
+            .with_result("getblockcount", 1u32)
+            .get_block_count();
+    }
+
+    #[test]
+    #[should_panic(expected = "getbalance was called but not stubbed")]
+    fn panics_on_unstubbed_method() {
+        let _ = BitcoinStubClient::new().get_balance();
+    }
+
+    #[test]
+    #[should_panic(expected = "getblockcount was called more often than stubbed")]
+    fn panics_when_sequence_is_exhausted() {
+        let stub = BitcoinStubClient::new()
+            .with_results("getblockcount", vec![Ok(rpc::BlockHeight::new(1))]);
+
+        let _ = stub.get_block_count();
+        let _ = stub.get_block_count();
+    }
+}
@@ -1,22 +1,29 @@
 use bitcoin::Address;
-use bitcoin_rpc_client::*;
-
+use bitcoincore::BitcoinCoreClient;
+use rpc;
+use BitcoinRpcApi;
+use BlockHash;
+use TransactionId;
+
+/// Creates the blocks, transactions and addresses tests need on a regtest node with a wallet.
+///
+/// Panics if the node fails an RPC, which fails the test.
 pub struct BitcoinCoreTestClient<'a> {
     pub client: &'a BitcoinCoreClient,
 }
 
 impl<'a> BitcoinCoreTestClient<'a> {
-    pub fn new(client: &'a BitcoinCoreClient) -> BitcoinCoreTestClient {
+    pub fn new(client: &'a BitcoinCoreClient) -> BitcoinCoreTestClient<'a> {
         BitcoinCoreTestClient { client }
     }
 
+    /// A mature output of a coinbase transaction paying to the wallet.
     pub fn a_utxo(&self) -> rpc::UnspentTransactionOutput {
         let _ = self.a_block(); // Need to generate a block first
 
         let mut utxos = self
             .client
             .list_unspent(rpc::TxOutConfirmations::AtLeast(6), None, None)
-            .unwrap()
             .unwrap();
 
         utxos.remove(0)
@@ -33,18 +40,17 @@ impl<'a> BitcoinCoreTestClient<'a> {
     }
 
     pub fn an_address(&self) -> Address {
-        self.client.get_new_address().unwrap().unwrap()
+        self.client.get_new_address().unwrap()
     }
 
+    /// Mines 101 blocks, which matures a coinbase output, and returns one in the middle.
     pub fn a_block(&self) -> rpc::Block<TransactionId> {
         self.client
             .generate(101)
-            .and_then(|response| {
-                let blocks = response.unwrap();
+            .and_then(|blocks| {
                 let block = blocks.get(50).unwrap();
                 self.client.get_block(block)
             })
             .unwrap()
-            .unwrap()
     }
 }
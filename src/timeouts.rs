@@ -0,0 +1,114 @@
+use payload::methods;
+use serde_json::Value;
+use std::{collections::BTreeMap, time::Duration};
+
+/// How long a client waits for the node.
+///
+/// The read timeout can be overridden per RPC method for calls that are known to take long,
+/// e.g. `scantxoutset`. `get_block` and `get_block_verbose` share the `getblock` override.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timeouts {
+    connect: Option<Duration>,
+    read: Option<Duration>,
+    methods: BTreeMap<String, Option<Duration>>,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Timeouts::new()
+    }
+}
+
+impl Timeouts {
+    pub fn new() -> Self {
+        Timeouts {
+            connect: Some(Duration::from_secs(10)),
+            read: Some(Duration::from_secs(30)),
+            methods: BTreeMap::new(),
+        }
+        .with_method_timeout("getblock", Some(Duration::from_secs(120)))
+        .with_method_timeout("rescanblockchain", Some(Duration::from_secs(3600)))
+        .with_method_timeout("scantxoutset", Some(Duration::from_secs(600)))
+    }
+
+    /// `None` waits forever.
+    pub fn with_connect_timeout(self, connect: Option<Duration>) -> Self {
+        Timeouts { connect, ..self }
+    }
+    /// `None` waits forever.
+    pub fn with_read_timeout(self, read: Option<Duration>) -> Self {
+        Timeouts { read, ..self }
+    }
+    /// Overrides the read timeout for all calls of `method`, `None` waits forever.
+    pub fn with_method_timeout(mut self, method: &str, timeout: Option<Duration>) -> Self {
+        self.methods.insert(method.to_string(), timeout);
+        self
+    }
+
+    pub(crate) fn connect(&self) -> Option<Duration> {
+        self.connect
+    }
+
+    /// The read timeout for a serialized request, for a batch the longest one of its requests.
+    pub(crate) fn read(&self, payload: &Value) -> Option<Duration> {
+        let timeouts = methods(payload)
+            .into_iter()
+            .map(|method| match self.methods.get(method) {
+                Some(timeout) => *timeout,
+                None => self.read,
+            })
+            .collect::<Vec<_>>();
+
+        if timeouts.contains(&None) {
+            return None;
+        }
+
+        timeouts.into_iter().max().unwrap_or(self.read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn a_request(method: &str) -> Value {
+        json!({"jsonrpc": "1.0", "id": "1", "method": method, "params": null})
+    }
+
+    #[test]
+    fn uses_method_timeout_if_overridden() {
+        let timeouts = Timeouts::new()
+            .with_read_timeout(Some(Duration::from_secs(5)))
+            .with_method_timeout("scantxoutset", Some(Duration::from_secs(60)));
+
+        assert_eq!(
+            timeouts.read(&a_request("getblockcount")),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(
+            timeouts.read(&a_request("scantxoutset")),
+            Some(Duration::from_secs(60))
+        );
+    }
+
+    #[test]
+    fn batch_uses_longest_timeout() {
+        let timeouts = Timeouts::new()
+            .with_read_timeout(Some(Duration::from_secs(5)))
+            .with_method_timeout("getblock", Some(Duration::from_secs(20)))
+            .with_method_timeout("rescanblockchain", None);
+
+        assert_eq!(
+            timeouts.read(&json!([a_request("getblockcount"), a_request("getblock")])),
+            Some(Duration::from_secs(20))
+        );
+        assert_eq!(
+            timeouts.read(&json!([
+                a_request("getblock"),
+                a_request("rescanblockchain")
+            ])),
+            None
+        );
+    }
+}
@@ -2,52 +2,58 @@ use bitcoin::Address;
 use bitcoin::Script;
 use types::script::ScriptType;
 
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct MultiSigAddress {
     pub address: Address,
     #[serde(rename = "redeemScript")]
     pub redeem_script: Script,
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct Label {
     pub name: String,
-    pub purpose: String,
+    /// Only returned before 0.20.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>,
 }
 
 /// Most of the Option<T> are due to different address formats
 /// Different fields are returned for P2PKH and P2SH addresses.
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct AddressValidationResult {
     #[serde(rename = "isvalid")]
     is_valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     address: Option<Address>,
-    #[serde(rename = "scriptPubKey")]
+    #[serde(rename = "scriptPubKey", skip_serializing_if = "Option::is_none")]
     script_pub_key: Option<String>,
-    #[serde(rename = "ismine")]
+    #[serde(rename = "ismine", skip_serializing_if = "Option::is_none")]
     is_mine: Option<bool>,
-    #[serde(rename = "iswatchonly")]
+    #[serde(rename = "iswatchonly", skip_serializing_if = "Option::is_none")]
     is_watch_only: Option<bool>,
-    #[serde(rename = "isscript")]
+    #[serde(rename = "isscript", skip_serializing_if = "Option::is_none")]
     is_script: Option<bool>,
-    #[serde(rename = "script")]
+    #[serde(rename = "script", skip_serializing_if = "Option::is_none")]
     script_type: Option<ScriptType>,
-    #[serde(rename = "hex")]
+    #[serde(rename = "hex", skip_serializing_if = "Option::is_none")]
     redeem_script: Option<Script>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     addresses: Option<Vec<Address>>,
-    #[serde(rename = "sigsrequired")]
+    #[serde(rename = "sigsrequired", skip_serializing_if = "Option::is_none")]
     sigs_required: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pubkey: Option<String>, //TODO: use PubkeyHash here
-    #[serde(rename = "iscompressed")]
+    #[serde(rename = "iscompressed", skip_serializing_if = "Option::is_none")]
     is_compressed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     account: Option<String>,
-    #[serde(rename = "hdkeypath")]
+    #[serde(rename = "hdkeypath", skip_serializing_if = "Option::is_none")]
     hd_key_path: Option<String>,
-    #[serde(rename = "hdmasterkeyid")]
+    #[serde(rename = "hdmasterkeyid", skip_serializing_if = "Option::is_none")]
     hd_masterkey_id: Option<String>,
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct AddressInfoResult {
     address: Address,
     #[serde(rename = "scriptPubKey")]
@@ -60,27 +66,35 @@ pub struct AddressInfoResult {
     is_script: bool,
     #[serde(rename = "iswitness")]
     is_witness: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     witness_version: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     witness_program: Option<String>,
-    #[serde(rename = "script")]
+    #[serde(rename = "script", skip_serializing_if = "Option::is_none")]
     script_type: Option<ScriptType>,
-    #[serde(rename = "hex")]
+    #[serde(rename = "hex", skip_serializing_if = "Option::is_none")]
     redeem_script: Option<Script>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pubkeys: Option<Vec<String>>,
-    #[serde(rename = "sigsrequired")]
+    #[serde(rename = "sigsrequired", skip_serializing_if = "Option::is_none")]
     signatures_required: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pubkey: Option<String>,
     // embedded: ignored
-    #[serde(rename = "iscompressed")]
+    #[serde(rename = "iscompressed", skip_serializing_if = "Option::is_none")]
     is_compressed: Option<bool>,
-    label: String,
+    /// Removed in 0.21.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     timestamp: Option<u64>,
-    #[serde(rename = "hdkeypath")]
+    #[serde(rename = "hdkeypath", skip_serializing_if = "Option::is_none")]
     hd_keypath: Option<String>,
-    #[serde(rename = "hdseedid")]
+    #[serde(rename = "hdseedid", skip_serializing_if = "Option::is_none")]
     hd_seed_id: Option<String>,
-    #[serde(rename = "hdmasterkeyid")]
+    #[serde(rename = "hdmasterkeyid", skip_serializing_if = "Option::is_none")]
     hd_master_key_id: Option<String>,
+    #[serde(with = "super::serde::labels")]
     labels: Vec<Label>,
 }
 
@@ -89,7 +103,9 @@ mod tests {
     extern crate hex;
 
     use super::*;
+    use quickcheck::{Arbitrary, Gen};
     use serde_json;
+    use types::arbitrary::*;
 
     #[test]
     fn can_deserialize_mainnet_p2pkh_address() {
@@ -256,4 +272,111 @@ mod tests {
             hd_masterkey_id: None,
         })
     }
+
+    impl Arbitrary for MultiSigAddress {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            MultiSigAddress {
+                address: address(g),
+                redeem_script: script(g),
+            }
+        }
+    }
+
+    impl Arbitrary for Label {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            Label {
+                name: String::arbitrary(g),
+                purpose: Arbitrary::arbitrary(g),
+            }
+        }
+    }
+
+    impl Arbitrary for AddressValidationResult {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            AddressValidationResult {
+                is_valid: bool::arbitrary(g),
+                address: option(g, address),
+                script_pub_key: option(g, hex),
+                is_mine: Arbitrary::arbitrary(g),
+                is_watch_only: Arbitrary::arbitrary(g),
+                is_script: Arbitrary::arbitrary(g),
+                script_type: Arbitrary::arbitrary(g),
+                redeem_script: option(g, script),
+                addresses: option(g, |g| vec(g, address)),
+                sigs_required: Arbitrary::arbitrary(g),
+                pubkey: option(g, hex),
+                is_compressed: Arbitrary::arbitrary(g),
+                account: Arbitrary::arbitrary(g),
+                hd_key_path: Arbitrary::arbitrary(g),
+                hd_masterkey_id: option(g, hex),
+            }
+        }
+    }
+
+    impl Arbitrary for AddressInfoResult {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            AddressInfoResult {
+                address: address(g),
+                script_pub_key: hex(g),
+                is_mine: bool::arbitrary(g),
+                is_watch_only: bool::arbitrary(g),
+                is_script: bool::arbitrary(g),
+                is_witness: bool::arbitrary(g),
+                witness_version: Arbitrary::arbitrary(g),
+                witness_program: option(g, hex),
+                script_type: Arbitrary::arbitrary(g),
+                redeem_script: option(g, script),
+                pubkeys: option(g, |g| vec(g, hex)),
+                signatures_required: Arbitrary::arbitrary(g),
+                pubkey: option(g, hex),
+                is_compressed: Arbitrary::arbitrary(g),
+                label: Arbitrary::arbitrary(g),
+                timestamp: Arbitrary::arbitrary(g),
+                hd_keypath: Arbitrary::arbitrary(g),
+                hd_seed_id: option(g, hex),
+                hd_master_key_id: option(g, hex),
+                labels: Arbitrary::arbitrary(g),
+            }
+        }
+    }
+
+    quickcheck! {
+        fn multi_sig_address_round_trips(address: MultiSigAddress) -> bool {
+            round_trips(&address)
+        }
+
+        fn label_round_trips(label: Label) -> bool {
+            round_trips(&label)
+        }
+
+        fn address_validation_result_round_trips(result: AddressValidationResult) -> bool {
+            round_trips(&result)
+        }
+
+        fn address_info_result_round_trips(result: AddressInfoResult) -> bool {
+            round_trips(&result)
+        }
+    }
+
+    #[test]
+    fn serializes_labels_like_bitcoind() {
+        let labels = vec![
+            Label {
+                name: String::from("savings"),
+                purpose: None,
+            },
+            Label {
+                name: String::from("change"),
+                purpose: Some(String::from("send")),
+            },
+        ];
+
+        let mut serializer = serde_json::Serializer::new(Vec::new());
+        super::super::serde::labels::serialize(&labels, &mut serializer).unwrap();
+
+        assert_eq!(
+            String::from_utf8(serializer.into_inner()).unwrap(),
+            r#"["savings",{"name":"change","purpose":"send"}]"#
+        );
+    }
 }
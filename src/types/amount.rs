@@ -0,0 +1,315 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    error::Error,
+    fmt,
+    iter::Sum,
+    ops::{Add, Neg, Sub},
+    str::FromStr,
+};
+
+const SAT_PER_BTC: i64 = 100_000_000;
+
+/// Beyond 2^53 satoshi, not every amount has its own `f64`, which is how JSON numbers are read.
+const MAX_EXACT_SAT: i64 = 1 << 53;
+
+/// An amount of bitcoin, counted in satoshi.
+///
+/// bitcoind represents amounts as JSON numbers of BTC with 8 decimals, this type converts them
+/// to and from satoshi without rounding errors. Amounts are signed because wallet RPCs report
+/// outgoing payments and fees as negative amounts.
+///
+/// Displayed and parsed like bitcoind does, e.g. `"0.00100000"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Amount(i64);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseAmountError {
+    /// Not a decimal number of bitcoin, e.g. `"1,5"` or `NaN`.
+    InvalidFormat(String),
+    /// More than 8 decimals, which would be fractions of a satoshi.
+    TooPrecise(String),
+    /// More than 2^53 satoshi, which can't be represented exactly as a JSON number.
+    TooBig(String),
+}
+
+impl Amount {
+    pub const ZERO: Amount = Amount(0);
+    pub const ONE_SAT: Amount = Amount(1);
+    pub const ONE_BTC: Amount = Amount(SAT_PER_BTC);
+    /// The 21 million bitcoin that will ever exist.
+    pub const MAX_MONEY: Amount = Amount(21_000_000 * SAT_PER_BTC);
+
+    pub fn from_sat(sat: i64) -> Self {
+        Amount(sat)
+    }
+
+    /// Rounds `btc` to the nearest satoshi.
+    ///
+    /// Every amount bitcoind sends as a JSON number of BTC with 8 decimals is converted exactly.
+    pub fn from_btc(btc: f64) -> Result<Self, ParseAmountError> {
+        if !btc.is_finite() {
+            return Err(ParseAmountError::InvalidFormat(btc.to_string()));
+        }
+
+        let sat = (btc * SAT_PER_BTC as f64).round();
+
+        if sat.abs() > MAX_EXACT_SAT as f64 {
+            return Err(ParseAmountError::TooBig(btc.to_string()));
+        }
+
+        Ok(Amount(sat as i64))
+    }
+
+    pub fn as_sat(self) -> i64 {
+        self.0
+    }
+
+    /// The amount in BTC, as close as an `f64` gets to it.
+    pub fn as_btc(self) -> f64 {
+        self.0 as f64 / SAT_PER_BTC as f64
+    }
+
+    pub fn checked_add(self, other: Amount) -> Option<Amount> {
+        self.0.checked_add(other.0).map(Amount)
+    }
+
+    pub fn checked_sub(self, other: Amount) -> Option<Amount> {
+        self.0.checked_sub(other.0).map(Amount)
+    }
+}
+
+impl Add for Amount {
+    type Output = Amount;
+
+    fn add(self, other: Amount) -> Amount {
+        Amount(self.0 + other.0)
+    }
+}
+
+impl Sub for Amount {
+    type Output = Amount;
+
+    fn sub(self, other: Amount) -> Amount {
+        Amount(self.0 - other.0)
+    }
+}
+
+impl Neg for Amount {
+    type Output = Amount;
+
+    fn neg(self) -> Amount {
+        Amount(-self.0)
+    }
+}
+
+impl Sum for Amount {
+    fn sum<I: Iterator<Item = Amount>>(iter: I) -> Amount {
+        iter.fold(Amount::ZERO, Add::add)
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let sat = self.0.unsigned_abs();
+
+        write!(
+            f,
+            "{}{}.{:08}",
+            sign,
+            sat / SAT_PER_BTC as u64,
+            sat % SAT_PER_BTC as u64
+        )
+    }
+}
+
+/// Parses a decimal number of BTC exactly, e.g. `"-0.5"` or `"21000000.00000000"`.
+impl FromStr for Amount {
+    type Err = ParseAmountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseAmountError::InvalidFormat(s.to_string());
+
+        let (negative, unsigned) = match s.strip_prefix('-') {
+            Some(unsigned) => (true, unsigned),
+            None => (false, s),
+        };
+        let (integer, fraction) = match unsigned.find('.') {
+            Some(dot) => (&unsigned[..dot], &unsigned[dot + 1..]),
+            None => (unsigned, ""),
+        };
+
+        let is_digits = |part: &str| part.bytes().all(|byte| byte.is_ascii_digit());
+        if integer.is_empty() && fraction.is_empty() || !is_digits(integer) || !is_digits(fraction)
+        {
+            return Err(invalid());
+        }
+
+        // Trailing zeros don't add precision
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.len() > 8 {
+            return Err(ParseAmountError::TooPrecise(s.to_string()));
+        }
+
+        let too_big = || ParseAmountError::TooBig(s.to_string());
+        let integer = match integer {
+            "" => 0,
+            integer => integer.parse::<i64>().map_err(|_| too_big())?,
+        };
+        let fraction = format!("{:0<8}", fraction).parse::<i64>().unwrap();
+        let sat = integer
+            .checked_mul(SAT_PER_BTC)
+            .and_then(|sat| sat.checked_add(fraction))
+            .filter(|sat| *sat <= MAX_EXACT_SAT)
+            .ok_or_else(too_big)?;
+
+        Ok(Amount(if negative { -sat } else { sat }))
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_f64(self.as_btc())
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let btc = f64::deserialize(deserializer)?;
+
+        Amount::from_btc(btc).map_err(de::Error::custom)
+    }
+}
+
+impl fmt::Display for ParseAmountError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseAmountError::InvalidFormat(ref amount) => {
+                write!(f, "invalid amount of bitcoin: {}", amount)
+            }
+            ParseAmountError::TooPrecise(ref amount) => {
+                write!(f, "amount has more than 8 decimals: {}", amount)
+            }
+            ParseAmountError::TooBig(ref amount) => write!(f, "amount is too big: {}", amount),
+        }
+    }
+}
+
+impl Error for ParseAmountError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::{Arbitrary, Gen};
+    use rand::Rng;
+    use serde_json;
+    use types::arbitrary::round_trips;
+
+    impl Arbitrary for Amount {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            let max = Amount::MAX_MONEY.as_sat();
+
+            Amount::from_sat(g.gen_range(-max, max + 1))
+        }
+    }
+
+    quickcheck! {
+        fn amount_round_trips(amount: Amount) -> bool {
+            round_trips(&amount)
+        }
+
+        fn amount_survives_conversion_to_btc(amount: Amount) -> bool {
+            Amount::from_btc(amount.as_btc()) == Ok(amount)
+        }
+
+        fn amount_parses_its_display(amount: Amount) -> bool {
+            amount.to_string().parse() == Ok(amount)
+        }
+    }
+
+    #[test]
+    fn deserializes_amounts_of_bitcoind_exactly() {
+        let amounts = serde_json::from_str::<Vec<Amount>>(
+            "[0.00000001, 0.29999999, 20999999.99999999, 21000000.00000000, -0.00001410, 50, 0]",
+        )
+        .unwrap();
+
+        assert_eq!(
+            amounts,
+            vec![
+                Amount::ONE_SAT,
+                Amount::from_sat(29_999_999),
+                Amount::MAX_MONEY - Amount::ONE_SAT,
+                Amount::MAX_MONEY,
+                Amount::from_sat(-1410),
+                Amount::from_sat(5_000_000_000),
+                Amount::ZERO,
+            ]
+        );
+    }
+
+    #[test]
+    fn serializes_as_btc() {
+        assert_eq!(
+            serde_json::to_string(&Amount::from_sat(123_456_789)).unwrap(),
+            "1.23456789"
+        );
+        assert_eq!(
+            serde_json::to_string(&Amount::MAX_MONEY).unwrap(),
+            "21000000.0"
+        );
+    }
+
+    #[test]
+    fn displays_8_decimals() {
+        assert_eq!(Amount::ONE_SAT.to_string(), "0.00000001");
+        assert_eq!(Amount::from_sat(-150_000_000).to_string(), "-1.50000000");
+        assert_eq!(Amount::ZERO.to_string(), "0.00000000");
+    }
+
+    #[test]
+    fn parses_decimal_btc() {
+        assert_eq!("1.5".parse(), Ok(Amount::from_sat(150_000_000)));
+        assert_eq!(".5".parse(), Ok(Amount::from_sat(50_000_000)));
+        assert_eq!("-0.00000001".parse(), Ok(-Amount::ONE_SAT));
+        assert_eq!("0.100000000".parse(), Ok(Amount::from_sat(10_000_000)));
+        assert_eq!(
+            "0.000000001".parse::<Amount>(),
+            Err(ParseAmountError::TooPrecise(String::from("0.000000001")))
+        );
+        assert_eq!(
+            "100000000".parse::<Amount>(),
+            Err(ParseAmountError::TooBig(String::from("100000000")))
+        );
+        for invalid in &["", ".", "-", "1,5", "1.5 BTC", "+1", "--1", "1e-8"] {
+            assert_eq!(
+                invalid.parse::<Amount>(),
+                Err(ParseAmountError::InvalidFormat(invalid.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_amounts_that_are_not_exact() {
+        assert!(Amount::from_btc(f64::NAN).is_err());
+        assert!(Amount::from_btc(f64::INFINITY).is_err());
+        assert!(serde_json::from_str::<Amount>("100000000").is_err());
+    }
+
+    #[test]
+    fn sums_up() {
+        let amounts = vec![Amount::ONE_BTC, Amount::ONE_SAT, -Amount::ONE_SAT];
+
+        assert_eq!(amounts.into_iter().sum::<Amount>(), Amount::ONE_BTC);
+        assert_eq!(
+            Amount::from_sat(i64::MAX).checked_add(Amount::ONE_SAT),
+            None
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use bitcoin::{network::constants::Network, util::address::Payload, Address, Script};
+use bitcoin_hashes::{hash160, sha256d, Hash};
+use quickcheck::{Arbitrary, Gen};
+use rand::{seq::SliceRandom, Rng};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json;
+use std::fmt::Debug;
+use std_hex;
+
+/// Whether `value` is the same after serializing it to JSON and back.
+pub fn round_trips<T: Serialize + DeserializeOwned + PartialEq + Debug>(value: &T) -> bool {
+    let json = serde_json::to_string(value).unwrap();
+
+    match serde_json::from_str::<T>(&json) {
+        Ok(ref deserialized) if deserialized == value => true,
+        outcome => {
+            eprintln!("{} deserialized to {:?}", json, outcome);
+            false
+        }
+    }
+}
+
+pub fn bytes<G: Gen>(g: &mut G, len: usize) -> Vec<u8> {
+    (0..len).map(|_| g.gen()).collect()
+}
+
+pub fn hex<G: Gen>(g: &mut G) -> String {
+    let len = g.gen_range(0, 80);
+
+    std_hex::encode(bytes(g, len))
+}
+
+pub fn hash<G: Gen>(g: &mut G) -> sha256d::Hash {
+    sha256d::Hash::from_slice(&bytes(g, 32)).unwrap()
+}
+
+pub fn script<G: Gen>(g: &mut G) -> Script {
+    Script::from(Vec::<u8>::arbitrary(g))
+}
+
+pub fn network<G: Gen>(g: &mut G) -> Network {
+    *[Network::Bitcoin, Network::Testnet, Network::Regtest]
+        .choose(g)
+        .unwrap()
+}
+
+/// Base58 addresses don't tell testnet and regtest apart, so only bech32 ones are on regtest.
+pub fn address<G: Gen>(g: &mut G) -> Address {
+    let network = *[Network::Bitcoin, Network::Testnet].choose(g).unwrap();
+
+    match g.gen_range(0, 3) {
+        0 => Address {
+            payload: Payload::PubkeyHash(hash160::Hash::from_slice(&bytes(g, 20)).unwrap()),
+            network,
+        },
+        1 => Address::p2sh(&script(g), network),
+        _ => Address::p2wsh(&script(g), self::network(g)),
+    }
+}
+
+pub fn option<G: Gen, T>(g: &mut G, value: impl FnOnce(&mut G) -> T) -> Option<T> {
+    if g.gen() {
+        Some(value(g))
+    } else {
+        None
+    }
+}
+
+pub fn vec<G: Gen, T>(g: &mut G, mut element: impl FnMut(&mut G) -> T) -> Vec<T> {
+    let len = g.gen_range(0, 4);
+
+    (0..len).map(|_| element(g)).collect()
+}
@@ -19,7 +19,7 @@ impl From<BlockHeight> for u32 {
     }
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct Block<T> {
     pub hash: BlockHash,
     pub confirmations: i32,
@@ -38,7 +38,9 @@ pub struct Block<T> {
     pub bits: String,
     pub difficulty: f64,
     pub chainwork: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub previousblockhash: Option<BlockHash>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub nextblockhash: Option<BlockHash>,
 }
 
@@ -46,7 +48,10 @@ pub struct Block<T> {
 mod tests {
     use super::*;
     use bitcoin_hashes::hex::FromHex;
+    use quickcheck::{Arbitrary, Gen};
+    use rpc::DecodedRawTransaction;
     use serde_json;
+    use types::arbitrary::*;
     use TransactionId;
 
     #[test]
@@ -117,4 +122,59 @@ mod tests {
             }
         )
     }
+
+    impl Arbitrary for BlockHeight {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            BlockHeight(u32::arbitrary(g))
+        }
+    }
+
+    fn block<G: Gen, T>(g: &mut G, transaction: impl FnMut(&mut G) -> T) -> Block<T> {
+        Block {
+            hash: hash(g),
+            confirmations: i32::arbitrary(g),
+            size: u32::arbitrary(g),
+            strippedsize: u32::arbitrary(g),
+            weight: u32::arbitrary(g),
+            height: u32::arbitrary(g),
+            version: u32::arbitrary(g),
+            version_hex: hex(g),
+            merkleroot: hex(g),
+            tx: vec(g, transaction),
+            time: u64::arbitrary(g),
+            mediantime: u64::arbitrary(g),
+            nonce: u32::arbitrary(g),
+            bits: hex(g),
+            difficulty: f64::arbitrary(g),
+            chainwork: hex(g),
+            previousblockhash: option(g, hash),
+            nextblockhash: option(g, hash),
+        }
+    }
+
+    impl Arbitrary for Block<TransactionId> {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            block(g, hash)
+        }
+    }
+
+    impl Arbitrary for Block<DecodedRawTransaction> {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            block(g, DecodedRawTransaction::arbitrary)
+        }
+    }
+
+    quickcheck! {
+        fn block_height_round_trips(height: BlockHeight) -> bool {
+            round_trips(&height)
+        }
+
+        fn block_round_trips(block: Block<TransactionId>) -> bool {
+            round_trips(&block)
+        }
+
+        fn verbose_block_round_trips(block: Block<DecodedRawTransaction>) -> bool {
+            round_trips(&block)
+        }
+    }
 }
@@ -1,26 +1,28 @@
 use bitcoin::network::constants::Network;
+use std::collections::BTreeMap;
 
-#[derive(Deserialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct SoftFork {
     pub id: String,
     pub version: u32,
     pub reject: Reject,
 }
 
-#[derive(Deserialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct Reject {
     pub status: bool,
 }
 
-#[derive(Deserialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct Bip9SoftFork {
     pub csv: Bip9SoftForkDetails,
     pub segwit: Bip9SoftForkDetails,
 }
 
-#[derive(Deserialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct Bip9SoftForkDetails {
     pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub bit: Option<u32>,
     #[serde(rename = "startTime")]
     // In regtest, startTime is -1
@@ -38,7 +40,39 @@ pub struct Bip9SoftForkDetails {
     */
 }
 
-#[derive(Deserialize, Debug, PartialEq)]
+/// Since 0.19, every deployment is listed by name with its activation details.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
+pub struct SoftForkDeployment {
+    /// `buried` or `bip9`
+    #[serde(rename = "type")]
+    pub deployment_type: String,
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bip9: Option<Bip9Deployment>,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
+pub struct Bip9Deployment {
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bit: Option<u32>,
+    pub start_time: i64,
+    pub timeout: u64,
+    pub since: u64,
+}
+
+/// The layout of `softforks` changed in 0.19.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum SoftForks {
+    /// Before 0.19, BIP9 deployments are listed separately in `bip9_softforks`.
+    Legacy(Vec<SoftFork>),
+    Deployments(BTreeMap<String, SoftForkDeployment>),
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct BlockchainInfo {
     #[serde(with = "super::serde::network")]
     pub chain: Network,
@@ -53,17 +87,29 @@ pub struct BlockchainInfo {
     pub chainwork: String,
     pub size_on_disk: u64,
     pub pruned: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub pruneheight: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub automatic_pruning: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub prune_target_size: Option<u64>,
-    pub softforks: Vec<SoftFork>,
-    pub bip9_softforks: Bip9SoftFork,
-    pub warnings: String,
+    /// Moved to `getdeploymentinfo` in 23.0.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub softforks: Option<SoftForks>,
+    /// Removed in 0.19.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bip9_softforks: Option<Bip9SoftFork>,
+    /// A single, possibly empty string before 28.0.
+    #[serde(deserialize_with = "super::serde::warnings::deserialize")]
+    pub warnings: Vec<String>,
 }
 #[cfg(test)]
 mod tests {
     use super::*;
+    use quickcheck::{Arbitrary, Gen};
+    use rand::Rng;
     use serde_json;
+    use types::arbitrary::*;
 
     #[test]
     fn can_deserialize_blockchain_response() {
@@ -141,7 +187,7 @@ mod tests {
                 pruneheight: None,
                 automatic_pruning: None,
                 prune_target_size: None,
-                softforks: vec![
+                softforks: Some(SoftForks::Legacy(vec![
                     SoftFork {
                         id: String::from("bip34"),
                         version: 2,
@@ -157,8 +203,8 @@ mod tests {
                         version: 4,
                         reject: Reject { status: false },
                     },
-                ],
-                bip9_softforks: Bip9SoftFork {
+                ])),
+                bip9_softforks: Some(Bip9SoftFork {
                     csv: Bip9SoftForkDetails {
                         status: String::from("defined"),
                         bit: None,
@@ -173,9 +219,217 @@ mod tests {
                         timeout: 9223372036854775807,
                         since: 0,
                     },
-                },
-                warnings: String::new(),
+                }),
+                warnings: Vec::new(),
             },
         )
     }
+
+    #[test]
+    fn can_deserialize_softforks_by_name() {
+        let json = r#"{
+        "chain": "regtest",
+        "blocks": 0,
+        "headers": 0,
+        "bestblockhash": "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206",
+        "difficulty": 4.656542373906925e-10,
+        "mediantime": 1296688602,
+        "verificationprogress": 1,
+        "initialblockdownload": true,
+        "chainwork": "0000000000000000000000000000000000000000000000000000000000000002",
+        "size_on_disk": 293,
+        "pruned": false,
+        "softforks": {
+            "bip34": {
+                "type": "buried",
+                "active": false,
+                "height": 500
+            },
+            "csv": {
+                "type": "bip9",
+                "bip9": {
+                    "status": "defined",
+                    "start_time": 0,
+                    "timeout": 9223372036854775807,
+                    "since": 0
+                },
+                "active": false
+            }
+        },
+        "warnings": ""
+}"#;
+        let blockchain: BlockchainInfo = serde_json::from_str(json).unwrap();
+
+        let mut deployments = BTreeMap::new();
+        deployments.insert(
+            String::from("bip34"),
+            SoftForkDeployment {
+                deployment_type: String::from("buried"),
+                active: false,
+                height: Some(500),
+                bip9: None,
+            },
+        );
+        deployments.insert(
+            String::from("csv"),
+            SoftForkDeployment {
+                deployment_type: String::from("bip9"),
+                active: false,
+                height: None,
+                bip9: Some(Bip9Deployment {
+                    status: String::from("defined"),
+                    bit: None,
+                    start_time: 0,
+                    timeout: 9223372036854775807,
+                    since: 0,
+                }),
+            },
+        );
+
+        assert_eq!(
+            blockchain.softforks,
+            Some(SoftForks::Deployments(deployments))
+        );
+        assert_eq!(blockchain.bip9_softforks, None);
+    }
+
+    #[test]
+    fn can_deserialize_warnings_as_string_or_list() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestStruct {
+            #[serde(deserialize_with = "super::super::serde::warnings::deserialize")]
+            warnings: Vec<String>,
+        }
+
+        let deserialize = |json| serde_json::from_str::<TestStruct>(json).unwrap().warnings;
+
+        assert_eq!(deserialize(r#"{"warnings": ""}"#), Vec::<String>::new());
+        assert_eq!(
+            deserialize(r#"{"warnings": "Unknown new rules activated"}"#),
+            vec![String::from("Unknown new rules activated")]
+        );
+        assert_eq!(
+            deserialize(r#"{"warnings": ["Unknown new rules activated", "Disk space is low"]}"#),
+            vec![
+                String::from("Unknown new rules activated"),
+                String::from("Disk space is low")
+            ]
+        );
+    }
+
+    impl Arbitrary for Reject {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            Reject {
+                status: bool::arbitrary(g),
+            }
+        }
+    }
+
+    impl Arbitrary for SoftFork {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            SoftFork {
+                id: String::arbitrary(g),
+                version: u32::arbitrary(g),
+                reject: Reject::arbitrary(g),
+            }
+        }
+    }
+
+    impl Arbitrary for Bip9SoftForkDetails {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            Bip9SoftForkDetails {
+                status: String::arbitrary(g),
+                bit: Arbitrary::arbitrary(g),
+                start_time: g.gen(),
+                timeout: g.gen(),
+                since: g.gen(),
+            }
+        }
+    }
+
+    impl Arbitrary for Bip9SoftFork {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            Bip9SoftFork {
+                csv: Bip9SoftForkDetails::arbitrary(g),
+                segwit: Bip9SoftForkDetails::arbitrary(g),
+            }
+        }
+    }
+
+    impl Arbitrary for Bip9Deployment {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            Bip9Deployment {
+                status: String::arbitrary(g),
+                bit: Arbitrary::arbitrary(g),
+                start_time: g.gen(),
+                timeout: g.gen(),
+                since: g.gen(),
+            }
+        }
+    }
+
+    impl Arbitrary for SoftForkDeployment {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            SoftForkDeployment {
+                deployment_type: String::arbitrary(g),
+                active: bool::arbitrary(g),
+                height: Arbitrary::arbitrary(g),
+                bip9: Arbitrary::arbitrary(g),
+            }
+        }
+    }
+
+    impl Arbitrary for SoftForks {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            if g.gen() {
+                SoftForks::Legacy(vec(g, SoftFork::arbitrary))
+            } else {
+                SoftForks::Deployments(
+                    vec(g, |g| {
+                        (String::arbitrary(g), SoftForkDeployment::arbitrary(g))
+                    })
+                    .into_iter()
+                    .collect(),
+                )
+            }
+        }
+    }
+
+    impl Arbitrary for BlockchainInfo {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            BlockchainInfo {
+                chain: network(g),
+                blocks: g.gen(),
+                headers: g.gen(),
+                bestblockhash: hex(g),
+                difficulty: f64::arbitrary(g),
+                mediantime: g.gen(),
+                verificationprogress: g.gen(),
+                initialblockdownload: bool::arbitrary(g),
+                chainwork: hex(g),
+                size_on_disk: g.gen(),
+                pruned: bool::arbitrary(g),
+                pruneheight: Arbitrary::arbitrary(g),
+                automatic_pruning: Arbitrary::arbitrary(g),
+                prune_target_size: Arbitrary::arbitrary(g),
+                softforks: Arbitrary::arbitrary(g),
+                bip9_softforks: Arbitrary::arbitrary(g),
+                warnings: Arbitrary::arbitrary(g),
+            }
+        }
+    }
+
+    quickcheck! {
+        fn soft_forks_round_trip(soft_forks: SoftForks) -> bool {
+            round_trips(&soft_forks)
+        }
+
+        fn bip9_soft_fork_round_trips(soft_fork: Bip9SoftFork) -> bool {
+            round_trips(&soft_fork)
+        }
+
+        fn blockchain_info_round_trips(info: BlockchainInfo) -> bool {
+            round_trips(&info)
+        }
+    }
 }
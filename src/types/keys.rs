@@ -1,8 +1,8 @@
 use bitcoin::util::key;
-use serde::{de, export::fmt, Deserialize, Deserializer, Serialize, Serializer};
-use std::{fmt as std_fmt, str::FromStr};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone)]
 pub struct PrivateKey(key::PrivateKey);
 
 impl From<key::PrivateKey> for PrivateKey {
@@ -17,6 +17,12 @@ impl From<PrivateKey> for key::PrivateKey {
     }
 }
 
+impl<'a> From<&'a PrivateKey> for key::PrivateKey {
+    fn from(p: &'a PrivateKey) -> Self {
+        p.0
+    }
+}
+
 impl Serialize for PrivateKey {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -54,8 +60,8 @@ impl<'de> Deserialize<'de> for PrivateKey {
     }
 }
 
-impl std_fmt::Debug for PrivateKey {
-    fn fmt(&self, f: &mut std_fmt::Formatter) -> std_fmt::Result {
+impl fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "PrivateKey {{ #REDACTED# }}")
     }
 }
@@ -63,7 +69,12 @@ impl std_fmt::Debug for PrivateKey {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use bitcoin::network::constants::Network;
+    use quickcheck::{Arbitrary, Gen};
+    use rand::seq::SliceRandom;
+    use secp256k1::SecretKey;
     use serde_json;
+    use types::arbitrary::*;
 
     #[test]
     fn serialize_private_key() {
@@ -80,4 +91,27 @@ mod tests {
 
         assert_eq!(priv_key.key, de_priv_key.key);
     }
+
+    impl Arbitrary for PrivateKey {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            let key = loop {
+                if let Ok(key) = SecretKey::from_slice(&bytes(g, 32)) {
+                    break key;
+                }
+            };
+
+            // WIF doesn't tell testnet and regtest apart
+            PrivateKey(key::PrivateKey {
+                compressed: bool::arbitrary(g),
+                network: *[Network::Bitcoin, Network::Testnet].choose(g).unwrap(),
+                key,
+            })
+        }
+    }
+
+    quickcheck! {
+        fn private_key_round_trips(private_key: PrivateKey) -> bool {
+            round_trips(&private_key)
+        }
+    }
 }
@@ -1,18 +1,23 @@
+#[cfg(test)]
+mod arbitrary;
 mod serde;
 
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
 pub mod address;
+pub mod amount;
 pub mod block;
 pub mod blockchain;
 pub mod keys;
 pub mod script;
 pub mod transaction;
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct Account(pub String);
 
 #[allow(non_camel_case_types)]
 // TODO: This enum is a bit weird. Clear it up once we have a better understanding of it
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
 pub enum SigHashType {
     #[serde(rename = "ALL")]
     All,
@@ -28,7 +33,115 @@ pub enum SigHashType {
     Single_AnyoneCanPay,
 }
 
+/// Serialized as the minimum number of confirmations, like the `minconf` parameters of bitcoind.
+#[derive(Debug, Clone, Copy)]
 pub enum TxOutConfirmations {
     Unconfirmed,
     AtLeast(i32),
 }
+
+impl TxOutConfirmations {
+    pub fn min_confirmations(self) -> i32 {
+        match self {
+            TxOutConfirmations::Unconfirmed => 0,
+            TxOutConfirmations::AtLeast(number) => number,
+        }
+    }
+}
+
+/// `AtLeast(0)` includes unconfirmed outputs, so it equals `Unconfirmed`.
+impl PartialEq for TxOutConfirmations {
+    fn eq(&self, other: &Self) -> bool {
+        self.min_confirmations() == other.min_confirmations()
+    }
+}
+
+impl Serialize for TxOutConfirmations {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_i32(self.min_confirmations())
+    }
+}
+
+impl<'de> Deserialize<'de> for TxOutConfirmations {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(match i32::deserialize(deserializer)? {
+            0 => TxOutConfirmations::Unconfirmed,
+            number => TxOutConfirmations::AtLeast(number),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::{Arbitrary, Gen};
+    use rand::{seq::SliceRandom, Rng};
+    use types::arbitrary::round_trips;
+
+    impl Arbitrary for Account {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            Account(String::arbitrary(g))
+        }
+    }
+
+    impl Arbitrary for SigHashType {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            *[
+                SigHashType::All,
+                SigHashType::None,
+                SigHashType::Single,
+                SigHashType::All_AnyoneCanPay,
+                SigHashType::None_AnyoneCanPay,
+                SigHashType::Single_AnyoneCanPay,
+            ]
+            .choose(g)
+            .unwrap()
+        }
+    }
+
+    impl Arbitrary for TxOutConfirmations {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            if g.gen() {
+                TxOutConfirmations::Unconfirmed
+            } else {
+                TxOutConfirmations::AtLeast(i32::arbitrary(g))
+            }
+        }
+    }
+
+    quickcheck! {
+        fn account_round_trips(account: Account) -> bool {
+            round_trips(&account)
+        }
+
+        fn sig_hash_type_round_trips(sig_hash_type: SigHashType) -> bool {
+            round_trips(&sig_hash_type)
+        }
+
+        fn tx_out_confirmations_round_trip(confirmations: TxOutConfirmations) -> bool {
+            round_trips(&confirmations)
+        }
+    }
+
+    #[test]
+    fn serializes_tx_out_confirmations_as_minconf() {
+        assert_eq!(
+            serde_json::to_string(&TxOutConfirmations::Unconfirmed).unwrap(),
+            "0"
+        );
+        assert_eq!(
+            serde_json::to_string(&TxOutConfirmations::AtLeast(6)).unwrap(),
+            "6"
+        );
+        assert_eq!(
+            TxOutConfirmations::AtLeast(0),
+            TxOutConfirmations::Unconfirmed
+        );
+    }
+}
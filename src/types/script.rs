@@ -4,11 +4,16 @@ use bitcoin::{Address, Script};
 pub struct ScriptPubKey {
     pub asm: String,
     pub hex: Script,
-    #[serde(rename = "reqSigs")]
+    #[serde(rename = "reqSigs", skip_serializing_if = "Option::is_none")]
     pub req_sigs: Option<u32>,
     #[serde(rename = "type")]
     pub script_type: ScriptType,
+    /// Removed in 22.0, replaced by `address`.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub addresses: Option<Vec<Address>>,
+    /// Since 22.0.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<Address>,
 }
 
 #[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
@@ -32,16 +37,23 @@ pub enum ScriptType {
     NullData,
     #[serde(rename = "witness_v0_scripthash")]
     WitnessScriptHash,
+    #[serde(rename = "witness_v1_taproot")]
+    WitnessV1Taproot,
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct DecodedScript {
     pub asm: String,
-    #[serde(rename = "type")]
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub script_type: Option<ScriptType>,
-    #[serde(rename = "reqSigs")]
+    #[serde(rename = "reqSigs", skip_serializing_if = "Option::is_none")]
     pub req_sigs: Option<u32>,
+    /// Removed in 22.0, replaced by `address`.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub addresses: Option<Vec<Address>>,
+    /// Since 22.0, only for scripts paying to a single address.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<Address>,
     pub p2sh: Address,
 }
 
@@ -49,8 +61,11 @@ pub struct DecodedScript {
 mod tests {
 
     use super::*;
+    use quickcheck::{Arbitrary, Gen};
+    use rand::seq::SliceRandom;
     use serde_json;
     use std::str::FromStr;
+    use types::arbitrary::*;
 
     #[test]
     fn can_deserialize_decoded_script_type() {
@@ -78,8 +93,64 @@ mod tests {
                 Address::from_str("mo1vzGwCzWqteip29vGWWW6MsEBREuzW94").unwrap(),
                 Address::from_str("mt17cV37fBqZsnMmrHnGCm9pM28R1kQdMG").unwrap(),
             ]),
+            address: None,
             p2sh: Address::from_str("2MyVxxgNBk5zHRPRY2iVjGRJHYZEp1pMCSq").unwrap(),
         })
     }
 
+    impl Arbitrary for ScriptType {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            [
+                ScriptType::PubKey,
+                ScriptType::PubKeyHash,
+                ScriptType::MultiSig,
+                ScriptType::NonStandard,
+                ScriptType::ScriptHash,
+                ScriptType::WitnessPubKeyHash,
+                ScriptType::WitnessUnknown,
+                ScriptType::NullData,
+                ScriptType::WitnessScriptHash,
+                ScriptType::WitnessV1Taproot,
+            ]
+            .choose(g)
+            .unwrap()
+            .clone()
+        }
+    }
+
+    impl Arbitrary for ScriptPubKey {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            ScriptPubKey {
+                asm: String::arbitrary(g),
+                hex: script(g),
+                req_sigs: Arbitrary::arbitrary(g),
+                script_type: ScriptType::arbitrary(g),
+                addresses: option(g, |g| vec(g, address)),
+                address: option(g, address),
+            }
+        }
+    }
+
+    impl Arbitrary for DecodedScript {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            DecodedScript {
+                asm: String::arbitrary(g),
+                script_type: Arbitrary::arbitrary(g),
+                req_sigs: Arbitrary::arbitrary(g),
+                addresses: option(g, |g| vec(g, address)),
+                address: option(g, address),
+                p2sh: address(g),
+            }
+        }
+    }
+
+    quickcheck! {
+        fn script_pub_key_round_trips(script_pub_key: ScriptPubKey) -> bool {
+            round_trips(&script_pub_key)
+        }
+
+        fn decoded_script_round_trips(script: DecodedScript) -> bool {
+            round_trips(&script)
+        }
+    }
 }
@@ -0,0 +1,45 @@
+use serde::{ser::SerializeSeq, Deserialize, Deserializer, Serializer};
+use types::address::Label;
+
+/// Labels without a purpose are written as plain names like 0.20 and later do.
+pub(crate) fn serialize<S>(labels: &[Label], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut seq = serializer.serialize_seq(Some(labels.len()))?;
+
+    for label in labels {
+        match label.purpose {
+            Some(_) => seq.serialize_element(label)?,
+            None => seq.serialize_element(&label.name)?,
+        }
+    }
+
+    seq.end()
+}
+
+/// Labels are objects with a name and a purpose before 0.20 and plain names since.
+pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Label>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawLabel {
+        Name(String),
+        Label(Label),
+    }
+
+    let labels = Vec::<RawLabel>::deserialize(deserializer)?;
+
+    Ok(labels
+        .into_iter()
+        .map(|label| match label {
+            RawLabel::Name(name) => Label {
+                name,
+                purpose: None,
+            },
+            RawLabel::Label(label) => label,
+        })
+        .collect())
+}
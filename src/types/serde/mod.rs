@@ -1 +1,3 @@
+pub mod labels;
 pub mod network;
+pub mod warnings;
@@ -1,7 +1,18 @@
 use bitcoin::network::constants::Network;
-use serde::{de, Deserializer};
+use serde::{de, Deserializer, Serializer};
 use std::fmt;
 
+pub(crate) fn serialize<S>(network: &Network, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(match *network {
+        Network::Bitcoin => "main",
+        Network::Testnet => "test",
+        Network::Regtest => "regtest",
+    })
+}
+
 pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Network, D::Error>
 where
     D: Deserializer<'de>,
@@ -0,0 +1,44 @@
+use serde::{de, Deserializer};
+use std::fmt;
+
+/// Warnings are a single string before 28.0 and a list of strings since.
+pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct Visitor;
+
+    impl<'de> de::Visitor<'de> for Visitor {
+        type Value = Vec<String>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a warning or a list of warnings")
+        }
+
+        fn visit_str<E>(self, value: &str) -> Result<Vec<String>, E>
+        where
+            E: de::Error,
+        {
+            if value.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            Ok(vec![value.to_string()])
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Vec<String>, A::Error>
+        where
+            A: de::SeqAccess<'de>,
+        {
+            let mut warnings = Vec::new();
+
+            while let Some(warning) = seq.next_element()? {
+                warnings.push(warning);
+            }
+
+            Ok(warnings)
+        }
+    }
+
+    deserializer.deserialize_any(Visitor)
+}
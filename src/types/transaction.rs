@@ -3,8 +3,9 @@ use bitcoin::{
     blockdata::{script::Script, transaction::Transaction as BitcoinTransaction},
     Address,
 };
+use serde::{de, Deserialize, Deserializer};
 use std::collections::HashMap;
-use types::script::ScriptPubKey;
+use types::{amount::Amount, script::ScriptPubKey};
 use BlockHash;
 use TransactionId;
 
@@ -17,16 +18,21 @@ impl From<BitcoinTransaction> for SerializedRawTransaction {
     }
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct Transaction {
-    pub amount: f64,
-    pub fee: Option<f64>,
+    pub amount: Amount,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee: Option<Amount>,
     pub confirmations: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub generated: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub blockhash: Option<BlockHash>,
     /// Unix Timestamp
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub blocktime: Option<u64>,
     /// Unix Timestamp
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub blockindex: Option<u64>,
     pub walletconflicts: Vec<TransactionId>,
     pub txid: TransactionId,
@@ -34,7 +40,9 @@ pub struct Transaction {
     pub time: u64,
     /// Unix Timestamp
     pub timereceived: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub to: Option<String>,
     #[serde(rename = "bip125-replaceable")]
     /// yes|no|unknown: TODO: Create enum if needed
@@ -43,21 +51,24 @@ pub struct Transaction {
     pub hex: SerializedRawTransaction,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct Detail {
     pub account: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub address: Option<Address>,
     /// send|receive|immature|generate|orphan TODO: Create enum if needed
     pub category: String,
-    pub amount: f64,
-    pub fee: Option<f64>,
+    pub amount: Amount,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee: Option<Amount>,
     pub vout: u32,
-    #[serde(rename = "involvesWatchonly")]
+    #[serde(rename = "involvesWatchonly", skip_serializing_if = "Option::is_none")]
     pub involves_watchonly: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub abandoned: Option<bool>,
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct DecodedRawTransaction {
     pub txid: TransactionId,
     pub hash: String,
@@ -67,9 +78,13 @@ pub struct DecodedRawTransaction {
     pub locktime: u32,
     pub vin: Vec<TransactionInput>,
     pub vout: Vec<TransactionOutput>,
+    /// Only sent for the transactions of a block requested with verbosity 2 from nodes since
+    /// 22.0, except for the coinbase transaction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee: Option<Amount>,
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct VerboseRawTransaction {
     pub txid: TransactionId,
     pub hash: String,
@@ -80,63 +95,109 @@ pub struct VerboseRawTransaction {
     pub vin: Vec<TransactionInput>,
     pub vout: Vec<TransactionOutput>,
     pub hex: SerializedRawTransaction,
-    pub blockhash: BlockHash,
-    pub confirmations: i32,
-    pub time: u64,
-    pub blocktime: u64,
+    /// The block fields are omitted for transactions in the mempool.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blockhash: Option<BlockHash>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmations: Option<i32>,
+    /// Unix Timestamp
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<u64>,
+    /// Unix Timestamp
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocktime: Option<u64>,
 }
 
 // TODO: Create serializer and deserializer that can create this struct from the only the hex string
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct ScriptSig {
     pub asm: String,
     pub hex: Script,
 }
 
-/// Transaction input can either be a regular transaction or a coinbase transaction.
-/// They have different fields, but most of the time, we will be interacting with regular transactions.
-/// For deserialization compatibility, we define all the fields as Option<T> and provide accessors.
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
-pub struct TransactionInput {
-    pub txid: Option<TransactionId>,
-    pub vout: Option<u32>,
-    #[serde(rename = "scriptSig")]
-    pub script_sig: Option<ScriptSig>,
-
-    pub coinbase: Option<String>,
-
-    pub sequence: u64,
+/// An input of a coinbase transaction or of a regular transaction.
+#[derive(Serialize, Debug, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum TransactionInput {
+    Coinbase {
+        coinbase: String,
+        /// Hex encoded witness stack items, the witness reserved value in blocks with a
+        /// witness commitment.
+        #[serde(rename = "txinwitness", skip_serializing_if = "Vec::is_empty")]
+        witness: Vec<String>,
+        sequence: u64,
+    },
+    Regular {
+        txid: TransactionId,
+        vout: u32,
+        #[serde(rename = "scriptSig")]
+        script_sig: ScriptSig,
+        /// Hex encoded witness stack items, empty for non-segwit inputs.
+        #[serde(rename = "txinwitness", skip_serializing_if = "Vec::is_empty")]
+        witness: Vec<String>,
+        sequence: u64,
+    },
 }
 
 impl TransactionInput {
-    pub fn txid(&self) -> &TransactionId {
-        self.txid.as_ref().expect("This is a coinbase transaction.")
+    pub fn is_coinbase(&self) -> bool {
+        match *self {
+            TransactionInput::Coinbase { .. } => true,
+            TransactionInput::Regular { .. } => false,
+        }
     }
 
-    pub fn vout(&self) -> u32 {
-        self.vout.expect("This is a coinbase transaction.")
+    pub fn sequence(&self) -> u64 {
+        match *self {
+            TransactionInput::Coinbase { sequence, .. }
+            | TransactionInput::Regular { sequence, .. } => sequence,
+        }
     }
+}
 
-    pub fn script_sig(&self) -> &ScriptSig {
-        self.script_sig
-            .as_ref()
-            .expect("This is a coinbase transaction.")
-    }
+impl<'de> Deserialize<'de> for TransactionInput {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        /// Both kinds of inputs as bitcoind serializes them, told apart by `coinbase`.
+        #[derive(Deserialize)]
+        struct RawInput {
+            coinbase: Option<String>,
+            txid: Option<TransactionId>,
+            vout: Option<u32>,
+            #[serde(rename = "scriptSig")]
+            script_sig: Option<ScriptSig>,
+            #[serde(rename = "txinwitness", default)]
+            witness: Vec<String>,
+            sequence: u64,
+        }
 
-    pub fn coinbase(&self) -> &str {
-        self.coinbase
-            .as_ref()
-            .expect("This is NOT a coinbase transaction.")
-    }
+        let input = RawInput::deserialize(deserializer)?;
 
-    pub fn sequence(&self) -> u64 {
-        self.sequence
+        if let Some(coinbase) = input.coinbase {
+            return Ok(TransactionInput::Coinbase {
+                coinbase,
+                witness: input.witness,
+                sequence: input.sequence,
+            });
+        }
+
+        Ok(TransactionInput::Regular {
+            txid: input.txid.ok_or_else(|| de::Error::missing_field("txid"))?,
+            vout: input.vout.ok_or_else(|| de::Error::missing_field("vout"))?,
+            script_sig: input
+                .script_sig
+                .ok_or_else(|| de::Error::missing_field("scriptSig"))?,
+            witness: input.witness,
+            sequence: input.sequence,
+        })
     }
 }
 
 #[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct TransactionOutput {
-    pub value: f64,
+    pub value: Amount,
     pub n: u32,
     #[serde(rename = "scriptPubKey")]
     pub script_pub_key: ScriptPubKey,
@@ -146,48 +207,53 @@ pub struct TransactionOutput {
 pub struct UnspentTransactionOutput {
     pub txid: TransactionId,
     pub vout: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub address: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub account: Option<String>,
     #[serde(rename = "scriptPubKey")]
     pub script_pub_key: Script,
+    #[serde(rename = "redeemScript", skip_serializing_if = "Option::is_none")]
     pub redeem_script: Option<Script>,
-    pub amount: f64,
+    pub amount: Amount,
     pub confirmations: i32,
     pub spendable: bool,
     pub solvable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub safe: Option<bool>,
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct NewTransactionInput {
     pub txid: TransactionId,
     pub vout: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub sequence: Option<u32>,
 }
 
 impl NewTransactionInput {
     pub fn from_utxo(utxo: &UnspentTransactionOutput) -> Self {
         NewTransactionInput {
-            txid: utxo.txid.clone(),
+            txid: utxo.txid,
             vout: utxo.vout,
             sequence: None,
         }
     }
 }
 
-pub type NewTransactionOutput = HashMap<Address, f64>;
+pub type NewTransactionOutput = HashMap<Address, Amount>;
 
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct TransactionOutputDetail {
     pub txid: TransactionId,
     pub vout: u32,
     #[serde(rename = "scriptPubKey")]
     pub script_pub_key: Script,
-    #[serde(rename = "redeemScript")]
+    #[serde(rename = "redeemScript", skip_serializing_if = "Option::is_none")]
     pub redeem_script: Option<Script>,
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct SigningError {
     pub txid: TransactionId,
     pub vout: u32,
@@ -198,14 +264,15 @@ pub struct SigningError {
     pub error: String,
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct SigningResult {
     pub hex: String,
     pub complete: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub errors: Option<Vec<SigningError>>,
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Default, Clone)]
 pub struct FundingOptions {
     #[serde(rename = "changeAddress", skip_serializing_if = "Option::is_none")]
     change_address: Option<Address>,
@@ -218,7 +285,7 @@ pub struct FundingOptions {
     #[serde(rename = "reserveChangeKey", skip_serializing_if = "Option::is_none")]
     reserve_change_key: Option<bool>,
     #[serde(rename = "feeRate", skip_serializing_if = "Option::is_none")]
-    fee_rate: Option<u32>,
+    fee_rate: Option<Amount>,
     #[serde(
         rename = "subtractFeeFromOutputs",
         skip_serializing_if = "Option::is_none"
@@ -269,7 +336,8 @@ impl FundingOptions {
             ..self
         }
     }
-    pub fn with_fee_rate(self, fee_rate: u32) -> Self {
+    /// The fee to pay per 1000 (virtual) bytes of the transaction, e.g. `0.0002` BTC/kB.
+    pub fn with_fee_rate(self, fee_rate: Amount) -> Self {
         FundingOptions {
             fee_rate: Some(fee_rate),
             ..self
@@ -283,10 +351,10 @@ impl FundingOptions {
     }
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct FundingResult {
     pub hex: SerializedRawTransaction,
-    pub fee: f64,
+    pub fee: Amount,
     // TODO: This is -1 if no change output was added. Add custom deserializer that converts to Option<u32>
     #[serde(rename = "changepos")]
     pub change_pos: i32,
@@ -299,10 +367,13 @@ mod tests {
     use bitcoin::util::misc::hex_bytes;
     use bitcoin::Address;
     use bitcoin_hashes::hex::FromHex;
-    use serde_json;
+    use quickcheck::{Arbitrary, Gen};
+    use rand::Rng;
+    use serde_json::{self, json};
     use std::collections::HashMap;
     use std::str::FromStr;
     use std_hex;
+    use types::arbitrary::*;
     use types::script::ScriptType;
 
     #[test]
@@ -361,20 +432,20 @@ mod tests {
             version: 1,
             locktime: 0,
             vin: vec![
-                TransactionInput {
-                    txid: Some(TransactionId::from_hex("2ac0daff49a4ff82a35a4864797f99f23c396b0529c5ba1e04b3d7b97521feba").unwrap()),
-                    vout: Some(0),
-                    script_sig: Some(ScriptSig {
+                TransactionInput::Regular {
+                    txid: TransactionId::from_hex("2ac0daff49a4ff82a35a4864797f99f23c396b0529c5ba1e04b3d7b97521feba").unwrap(),
+                    vout: 0,
+                    script_sig: ScriptSig {
                         asm: "3044022013d212c22f0b46bb33106d148493b9a9723adb2c3dd3a3ebe3a9c9e3b95d8cb00220461661710202fbab550f973068af45c294667fc4dc526627a7463eb23ab39e9b[ALL] 0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8".to_string(),
                         hex: Script::from(std_hex::decode("473044022013d212c22f0b46bb33106d148493b9a9723adb2c3dd3a3ebe3a9c9e3b95d8cb00220461661710202fbab550f973068af45c294667fc4dc526627a7463eb23ab39e9b01410479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8").unwrap()),
-                    }),
-                    coinbase: None,
+                    },
+                    witness: vec![],
                     sequence: 4294967295,
                 }
             ],
             vout: vec![
                 TransactionOutput {
-                    value: 0.06990000,
+                    value: Amount::from_sat(6_990_000),
                     n: 0,
                     script_pub_key: ScriptPubKey {
                         asm: "OP_DUP OP_HASH160 01b81d5fa1e55e069e3cc2db9c19e2e80358f306 OP_EQUALVERIFY OP_CHECKSIG".to_string(),
@@ -384,9 +455,11 @@ mod tests {
                         addresses: Some(vec![
                             Address::from_str("1A6Ei5cRfDJ8jjhwxfzLJph8B9ZEthR9Z").unwrap()
                         ]),
+                        address: None,
                     },
                 }
             ],
+            fee: None,
         })
     }
 
@@ -420,6 +493,9 @@ mod tests {
           "vin": [
             {
               "coinbase": "03142d010101",
+              "txinwitness": [
+                "0000000000000000000000000000000000000000000000000000000000000000"
+              ],
               "sequence": 4294967295
             }
           ],
@@ -465,17 +541,15 @@ mod tests {
             version: 2,
             locktime: 0,
             vin: vec![
-                TransactionInput {
-                    txid: None,
-                    vout: None,
-                    script_sig: None,
-                    coinbase: Some(String::from("03142d010101")),
+                TransactionInput::Coinbase {
+                    coinbase: String::from("03142d010101"),
+                    witness: vec![String::from("0000000000000000000000000000000000000000000000000000000000000000")],
                     sequence: 4294967295,
                 }
             ],
             vout: vec![
                 TransactionOutput {
-                    value: 0.0,
+                    value: Amount::ZERO,
                     n: 0,
                     script_pub_key: ScriptPubKey {
                         asm: "039b0e80cdda15ac2164392dfaf4f3eb36dd914dcb1c405eec3dd8c9ebf6c13fc1 OP_CHECKSIG".to_string(),
@@ -485,10 +559,11 @@ mod tests {
                         addresses: Some(vec![
                             Address::from_str("my9XdXbMLZm3v8uqGLuPRKatWjnpXw2boX").unwrap()
                         ]),
+                        address: None,
                     },
                 },
                 TransactionOutput {
-                    value: 0.0,
+                    value: Amount::ZERO,
                     n: 1,
                     script_pub_key: ScriptPubKey {
                         asm: "OP_RETURN aa21a9ede2f61c3f71d1defd3fa999dfa36953755c690689799962b48bebd836974e8cf9".to_string(),
@@ -496,14 +571,15 @@ mod tests {
                         req_sigs: None,
                         script_type: ScriptType::NullData,
                         addresses: None,
+                        address: None,
                     },
                 }
             ],
             hex: SerializedRawTransaction(String::from("020000000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0603142d010101ffffffff0200000000000000002321039b0e80cdda15ac2164392dfaf4f3eb36dd914dcb1c405eec3dd8c9ebf6c13fc1ac0000000000000000266a24aa21a9ede2f61c3f71d1defd3fa999dfa36953755c690689799962b48bebd836974e8cf90120000000000000000000000000000000000000000000000000000000000000000000000000")),
-            blockhash: BlockHash::from_hex("796d7a2dbb1213b65dc2f7170575755efdfae8340b2183e971ed5a89113bbedf").unwrap(),
-            confirmations: 9,
-            time: 1525393130,
-            blocktime: 1525393130,
+            blockhash: Some(BlockHash::from_hex("796d7a2dbb1213b65dc2f7170575755efdfae8340b2183e971ed5a89113bbedf").unwrap()),
+            confirmations: Some(9),
+            time: Some(1525393130),
+            blocktime: Some(1525393130),
         })
     }
 
@@ -539,7 +615,7 @@ mod tests {
                     std_hex::decode("76a9140dfc8bafc8419853b34d5e072ad37d1a5159f58488ac").unwrap()
                 ),
                 redeem_script: None,
-                amount: 0.0001,
+                amount: Amount::from_sat(10_000),
                 confirmations: 6210,
                 spendable: true,
                 solvable: true,
@@ -548,12 +624,33 @@ mod tests {
         )
     }
 
+    #[test]
+    fn should_deserialize_p2sh_unspent_transaction_output_and_serialize_it_back() {
+        let json = json!({
+            "txid": "d54994ece1d11b19785c7248868696250ab195605b469632b7bd68130e880c9a",
+            "vout": 0,
+            "address": "2MyVxxgNBk5zHRPRY2iVjGRJHYZEp1pMCSq",
+            "scriptPubKey": "a914449a731d7b281bda8c837ac93f8be4eafc65b3d687",
+            "redeemScript": "522103ede722780d27b05f0b1169efc90fa15a601a32fc6c3295114500c586831b6aaf2102ecd2d250a76d204011de6bc365a56033b9b3a149f679bc17205555d3c2b2854f21022d609d2f0d359e5bc0e5d0ea20ff9f5d3396cb5b1906aa9c56a0e7b5edc0c5d553ae",
+            "amount": 0.5,
+            "confirmations": 1,
+            "spendable": true,
+            "solvable": true,
+            "safe": true
+        });
+
+        let utxo: UnspentTransactionOutput = serde_json::from_value(json.clone()).unwrap();
+
+        assert!(utxo.redeem_script.is_some());
+        assert_eq!(serde_json::to_value(&utxo).unwrap(), json);
+    }
+
     #[test]
     fn new_transaction_output_should_serialize_to_object() {
         let mut output: NewTransactionOutput = HashMap::new();
         output.insert(
             Address::from_str("mgnucj8nYqdrPFh2JfZSB1NmUThUGnmsqe").unwrap(),
-            10.12345,
+            Amount::from_sat(1_012_345_000),
         );
 
         let actual_json = serde_json::to_string(&output).unwrap();
@@ -561,4 +658,301 @@ mod tests {
 
         assert_eq!(actual_json, expected_json)
     }
+
+    #[test]
+    fn funding_options_should_serialize_fee_rate_in_btc() {
+        let options = FundingOptions::new().with_fee_rate(Amount::from_sat(20_000));
+
+        let actual_json = serde_json::to_string(&options).unwrap();
+
+        assert_eq!(actual_json, r#"{"feeRate":0.0002}"#)
+    }
+
+    #[test]
+    fn should_deserialize_segwit_input_and_serialize_it_back() {
+        let json = r#"{"txid":"2ac0daff49a4ff82a35a4864797f99f23c396b0529c5ba1e04b3d7b97521feba","vout":1,"scriptSig":{"asm":"","hex":""},"txinwitness":["3044022010d3b0f0e48977b5c7af7f6a0839a8ed24cd760c4e95668ed7b3275fca727360022007a27825d82a1e69bff2e8cbf195aa4280c214f1cf7650afb6fa2eb49a97650401","036bc4598b0de6ac9c560f1322ce86a0bf27e934837ac86196337db06002c3a352"],"sequence":4294967294}"#;
+
+        let input: TransactionInput = serde_json::from_str(json).unwrap();
+
+        match input {
+            TransactionInput::Regular {
+                vout, ref witness, ..
+            } => {
+                assert_eq!(vout, 1);
+                assert_eq!(witness.len(), 2);
+            }
+            ref coinbase => panic!("expected regular input but got {:?}", coinbase),
+        }
+        assert_eq!(input.sequence(), 4294967294);
+        assert_eq!(serde_json::to_string(&input).unwrap(), json);
+    }
+
+    #[test]
+    fn should_fail_on_input_without_coinbase_or_previous_output() {
+        let json = r#"{"vout":0,"scriptSig":{"asm":"","hex":""},"sequence":4294967295}"#;
+
+        let error = serde_json::from_str::<TransactionInput>(json).unwrap_err();
+
+        assert!(error.to_string().contains("missing field `txid`"));
+    }
+
+    impl Arbitrary for SerializedRawTransaction {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            SerializedRawTransaction(hex(g))
+        }
+    }
+
+    impl Arbitrary for Detail {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            Detail {
+                account: String::arbitrary(g),
+                address: option(g, address),
+                category: String::arbitrary(g),
+                amount: Amount::arbitrary(g),
+                fee: option(g, Amount::arbitrary),
+                vout: u32::arbitrary(g),
+                involves_watchonly: Arbitrary::arbitrary(g),
+                abandoned: Arbitrary::arbitrary(g),
+            }
+        }
+    }
+
+    impl Arbitrary for Transaction {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            Transaction {
+                amount: Amount::arbitrary(g),
+                fee: option(g, Amount::arbitrary),
+                confirmations: u32::arbitrary(g),
+                generated: Arbitrary::arbitrary(g),
+                blockhash: option(g, hash),
+                blocktime: Arbitrary::arbitrary(g),
+                blockindex: Arbitrary::arbitrary(g),
+                walletconflicts: vec(g, hash),
+                txid: hash(g),
+                time: u64::arbitrary(g),
+                timereceived: u64::arbitrary(g),
+                comment: Arbitrary::arbitrary(g),
+                to: Arbitrary::arbitrary(g),
+                bip125_replaceable: String::arbitrary(g),
+                details: Arbitrary::arbitrary(g),
+                hex: SerializedRawTransaction::arbitrary(g),
+            }
+        }
+    }
+
+    impl Arbitrary for ScriptSig {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            ScriptSig {
+                asm: String::arbitrary(g),
+                hex: script(g),
+            }
+        }
+    }
+
+    impl Arbitrary for TransactionInput {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            if g.gen() {
+                TransactionInput::Coinbase {
+                    coinbase: hex(g),
+                    witness: vec(g, hex),
+                    sequence: u64::arbitrary(g),
+                }
+            } else {
+                TransactionInput::Regular {
+                    txid: hash(g),
+                    vout: u32::arbitrary(g),
+                    script_sig: ScriptSig::arbitrary(g),
+                    witness: vec(g, hex),
+                    sequence: u64::arbitrary(g),
+                }
+            }
+        }
+    }
+
+    impl Arbitrary for TransactionOutput {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            TransactionOutput {
+                value: Amount::arbitrary(g),
+                n: u32::arbitrary(g),
+                script_pub_key: Arbitrary::arbitrary(g),
+            }
+        }
+    }
+
+    impl Arbitrary for DecodedRawTransaction {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            DecodedRawTransaction {
+                txid: hash(g),
+                hash: hex(g),
+                size: u32::arbitrary(g),
+                vsize: u32::arbitrary(g),
+                version: u32::arbitrary(g),
+                locktime: u32::arbitrary(g),
+                vin: Arbitrary::arbitrary(g),
+                vout: Arbitrary::arbitrary(g),
+                fee: option(g, Amount::arbitrary),
+            }
+        }
+    }
+
+    impl Arbitrary for VerboseRawTransaction {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            VerboseRawTransaction {
+                txid: hash(g),
+                hash: hex(g),
+                size: u32::arbitrary(g),
+                vsize: u32::arbitrary(g),
+                version: u32::arbitrary(g),
+                locktime: u32::arbitrary(g),
+                vin: Arbitrary::arbitrary(g),
+                vout: Arbitrary::arbitrary(g),
+                hex: SerializedRawTransaction::arbitrary(g),
+                blockhash: option(g, hash),
+                confirmations: Arbitrary::arbitrary(g),
+                time: Arbitrary::arbitrary(g),
+                blocktime: Arbitrary::arbitrary(g),
+            }
+        }
+    }
+
+    impl Arbitrary for UnspentTransactionOutput {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            UnspentTransactionOutput {
+                txid: hash(g),
+                vout: u32::arbitrary(g),
+                address: option(g, address),
+                account: Arbitrary::arbitrary(g),
+                script_pub_key: script(g),
+                redeem_script: option(g, script),
+                amount: Amount::arbitrary(g),
+                confirmations: i32::arbitrary(g),
+                spendable: bool::arbitrary(g),
+                solvable: bool::arbitrary(g),
+                safe: Arbitrary::arbitrary(g),
+            }
+        }
+    }
+
+    impl Arbitrary for NewTransactionInput {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            NewTransactionInput {
+                txid: hash(g),
+                vout: u32::arbitrary(g),
+                sequence: Arbitrary::arbitrary(g),
+            }
+        }
+    }
+
+    /// `NewTransactionOutput` is an alias of a map with keys that aren't `Arbitrary`.
+    #[derive(Debug, Clone)]
+    struct NewTransactionOutputs(NewTransactionOutput);
+
+    impl Arbitrary for NewTransactionOutputs {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            NewTransactionOutputs(
+                vec(g, |g| (address(g), Amount::arbitrary(g)))
+                    .into_iter()
+                    .collect(),
+            )
+        }
+    }
+
+    impl Arbitrary for TransactionOutputDetail {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            TransactionOutputDetail {
+                txid: hash(g),
+                vout: u32::arbitrary(g),
+                script_pub_key: script(g),
+                redeem_script: option(g, script),
+            }
+        }
+    }
+
+    impl Arbitrary for SigningError {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            SigningError {
+                txid: hash(g),
+                vout: u32::arbitrary(g),
+                script_sig: hex(g),
+                sequence: u32::arbitrary(g),
+                error: String::arbitrary(g),
+            }
+        }
+    }
+
+    impl Arbitrary for SigningResult {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            SigningResult {
+                hex: hex(g),
+                complete: bool::arbitrary(g),
+                errors: Arbitrary::arbitrary(g),
+            }
+        }
+    }
+
+    impl Arbitrary for FundingOptions {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            FundingOptions {
+                change_address: option(g, address),
+                change_position: Arbitrary::arbitrary(g),
+                include_watching: Arbitrary::arbitrary(g),
+                lock_unspents: Arbitrary::arbitrary(g),
+                reserve_change_key: Arbitrary::arbitrary(g),
+                fee_rate: option(g, Amount::arbitrary),
+                subtract_fee_from_outputs: Arbitrary::arbitrary(g),
+            }
+        }
+    }
+
+    impl Arbitrary for FundingResult {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            FundingResult {
+                hex: SerializedRawTransaction::arbitrary(g),
+                fee: Amount::arbitrary(g),
+                change_pos: i32::arbitrary(g),
+            }
+        }
+    }
+
+    quickcheck! {
+        fn transaction_round_trips(transaction: Transaction) -> bool {
+            round_trips(&transaction)
+        }
+
+        fn decoded_raw_transaction_round_trips(transaction: DecodedRawTransaction) -> bool {
+            round_trips(&transaction)
+        }
+
+        fn verbose_raw_transaction_round_trips(transaction: VerboseRawTransaction) -> bool {
+            round_trips(&transaction)
+        }
+
+        fn unspent_transaction_output_round_trips(utxo: UnspentTransactionOutput) -> bool {
+            round_trips(&utxo)
+        }
+
+        fn new_transaction_input_round_trips(input: NewTransactionInput) -> bool {
+            round_trips(&input)
+        }
+
+        fn new_transaction_output_round_trips(outputs: NewTransactionOutputs) -> bool {
+            round_trips(&outputs.0)
+        }
+
+        fn transaction_output_detail_round_trips(detail: TransactionOutputDetail) -> bool {
+            round_trips(&detail)
+        }
+
+        fn signing_result_round_trips(result: SigningResult) -> bool {
+            round_trips(&result)
+        }
+
+        fn funding_options_round_trip(options: FundingOptions) -> bool {
+            round_trips(&options)
+        }
+
+        fn funding_result_round_trips(result: FundingResult) -> bool {
+            round_trips(&result)
+        }
+    }
 }
@@ -0,0 +1,78 @@
+use std::fmt;
+
+/// The version of Bitcoin Core a node runs, as reported by `getnetworkinfo`.
+///
+/// Encoded like bitcoind does: `170100` is 0.17.1, `220000` is 22.0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeVersion(pub u32);
+
+impl NodeVersion {
+    const V0_16: NodeVersion = NodeVersion(160_000);
+    const V0_17: NodeVersion = NodeVersion(170_000);
+    const V0_19: NodeVersion = NodeVersion(190_000);
+
+    /// `generate` was removed in favor of `generatetoaddress`.
+    pub(crate) fn has_generate(self) -> bool {
+        self < Self::V0_19
+    }
+
+    /// `getnewaddress` accepts an address type, `getaddressinfo` exists.
+    pub(crate) fn has_address_types(self) -> bool {
+        self >= Self::V0_16
+    }
+
+    /// `signrawtransaction` was split into `signrawtransactionwithkey` and
+    /// `signrawtransactionwithwallet`.
+    pub(crate) fn has_sign_raw_transaction_with_key(self) -> bool {
+        self >= Self::V0_17
+    }
+}
+
+impl fmt::Display for NodeVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let major = self.0 / 10_000;
+        let minor = self.0 / 100 % 100;
+        let patch = self.0 % 100;
+
+        // Releases before 22.0 were numbered 0.x.y
+        if major < 22 {
+            write!(f, "0.")?;
+        }
+        write!(f, "{}.{}", major, minor)?;
+        if patch > 0 {
+            write!(f, ".{}", patch)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The part of the `getnetworkinfo` response needed to detect the version.
+#[derive(Deserialize, Debug)]
+pub(crate) struct NetworkInfo {
+    pub version: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn displays_like_release_names() {
+        assert_eq!(NodeVersion(170_100).to_string(), "0.17.1");
+        assert_eq!(NodeVersion(190_000).to_string(), "0.19.0");
+        assert_eq!(NodeVersion(210_001).to_string(), "0.21.0.1");
+        assert_eq!(NodeVersion(220_000).to_string(), "22.0");
+        assert_eq!(NodeVersion(250_100).to_string(), "25.1");
+    }
+
+    #[test]
+    fn detects_capabilities() {
+        assert!(NodeVersion(180_100).has_generate());
+        assert!(!NodeVersion(190_000).has_generate());
+        assert!(!NodeVersion(150_100).has_address_types());
+        assert!(NodeVersion(160_000).has_address_types());
+        assert!(!NodeVersion(160_300).has_sign_raw_transaction_with_key());
+        assert!(NodeVersion(170_000).has_sign_raw_transaction_with_key());
+    }
+}
@@ -0,0 +1,174 @@
+use payload::methods;
+use serde_json::Value;
+
+/// RPCs that operate on a wallet and hence have to be sent to the wallet endpoint
+/// if the node has more than one wallet loaded.
+///
+/// The wallet RPCs listed by `help` of Bitcoin Core 28, except for the ones managing wallets
+/// such as `createwallet` or `listwallets`, which are sent to the node.
+const WALLET_METHODS: &[&str] = &[
+    "abandontransaction",
+    "abortrescan",
+    "addmultisigaddress",
+    "backupwallet",
+    "bumpfee",
+    "createwalletdescriptor",
+    "dumpprivkey",
+    "dumpwallet",
+    "encryptwallet",
+    "fundrawtransaction",
+    "generate",
+    "getaddressesbylabel",
+    "getaddressinfo",
+    "getbalance",
+    "getbalances",
+    "gethdkeys",
+    "getnewaddress",
+    "getrawchangeaddress",
+    "getreceivedbyaddress",
+    "getreceivedbylabel",
+    "gettransaction",
+    "getunconfirmedbalance",
+    "getwalletinfo",
+    "importaddress",
+    "importdescriptors",
+    "importmulti",
+    "importprivkey",
+    "importprunedfunds",
+    "importpubkey",
+    "importwallet",
+    "keypoolrefill",
+    "listaddressgroupings",
+    "listdescriptors",
+    "listlabels",
+    "listlockunspent",
+    "listreceivedbyaddress",
+    "listreceivedbylabel",
+    "listsinceblock",
+    "listtransactions",
+    "listunspent",
+    "lockunspent",
+    "migratewallet",
+    "newkeypool",
+    "psbtbumpfee",
+    "removeprunedfunds",
+    "rescanblockchain",
+    "send",
+    "sendall",
+    "sendmany",
+    "sendtoaddress",
+    "sethdseed",
+    "setlabel",
+    "settxfee",
+    "setwalletflag",
+    "signmessage",
+    "signrawtransactionwithwallet",
+    "simulaterawtransaction",
+    "unloadwallet",
+    "upgradewallet",
+    "walletcreatefundedpsbt",
+    "walletdisplayaddress",
+    "walletlock",
+    "walletpassphrase",
+    "walletpassphrasechange",
+    "walletprocesspsbt",
+];
+
+/// Wallet RPCs of Bitcoin Core 0.17 that were removed since.
+const LEGACY_WALLET_METHODS: &[&str] = &[
+    "addwitnessaddress",
+    "getaccount",
+    "getaccountaddress",
+    "getaddressesbyaccount",
+    "getreceivedbyaccount",
+    "listaccounts",
+    "listreceivedbyaccount",
+    "move",
+    "resendwallettransactions",
+    "sendfrom",
+    "setaccount",
+    "signrawtransaction",
+];
+
+pub(crate) fn is_wallet_method(method: &str) -> bool {
+    WALLET_METHODS.contains(&method) || LEGACY_WALLET_METHODS.contains(&method)
+}
+
+/// Whether a serialized request, or any request of a batch, has to be sent to the wallet endpoint.
+pub(crate) fn targets_wallet(payload: &Value) -> bool {
+    methods(payload).into_iter().any(is_wallet_method)
+}
+
+/// The endpoint bitcoind serves the RPCs of wallet `name` on.
+pub(crate) fn wallet_url(node_url: &str, name: &str) -> String {
+    format!(
+        "{}/wallet/{}",
+        node_url.trim_end_matches('/'),
+        percent_encode(name)
+    )
+}
+
+fn percent_encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn builds_wallet_url() {
+        assert_eq!(
+            wallet_url("http://localhost:18443", "alice"),
+            "http://localhost:18443/wallet/alice"
+        );
+        assert_eq!(
+            wallet_url("http://localhost:18443/", "my wallet/1"),
+            "http://localhost:18443/wallet/my%20wallet%2F1"
+        );
+        assert_eq!(
+            wallet_url("http://localhost:18443", ""),
+            "http://localhost:18443/wallet/"
+        );
+    }
+
+    #[test]
+    fn routes_wallet_methods_only() {
+        let get_balance =
+            json!({"jsonrpc": "1.0", "id": "1", "method": "getbalance", "params": null});
+        let get_block_count =
+            json!({"jsonrpc": "1.0", "id": "1", "method": "getblockcount", "params": null});
+
+        assert!(targets_wallet(&get_balance));
+        assert!(!targets_wallet(&get_block_count));
+        assert!(targets_wallet(&json!([
+            get_block_count.clone(),
+            get_balance
+        ])));
+        assert!(!targets_wallet(&json!([get_block_count])));
+    }
+
+    #[test]
+    fn routes_wallet_methods_of_all_versions() {
+        for method in &["getbalances", "sendall", "listdescriptors", "getaccount"] {
+            assert!(is_wallet_method(method), "{}", method);
+        }
+        for method in &[
+            "createwallet",
+            "loadwallet",
+            "listwallets",
+            "getblockchaininfo",
+        ] {
+            assert!(!is_wallet_method(method), "{}", method);
+        }
+    }
+}
@@ -0,0 +1,63 @@
+#![allow(deprecated)]
+extern crate bitcoin_rpc_client;
+extern crate futures;
+extern crate jsonrpc_client;
+#[macro_use]
+extern crate log;
+extern crate bitcoin;
+extern crate testcontainers;
+extern crate tokio;
+
+use bitcoin_rpc_client::*;
+use common::{assert::assert_successful_async_result, test_lifecycle::setup};
+use futures::Future;
+
+#[allow(dead_code)]
+mod common;
+
+#[test]
+fn get_block_count() {
+    setup();
+    assert_successful_async_result(AsyncBitcoinCoreClient::get_block_count)
+}
+
+#[test]
+fn get_blockchain_info() {
+    setup();
+    assert_successful_async_result(AsyncBitcoinCoreClient::get_blockchain_info)
+}
+
+#[test]
+fn generate() {
+    setup();
+    assert_successful_async_result(|client| client.generate(1))
+}
+
+#[test]
+fn get_block() {
+    setup();
+
+    assert_successful_async_result(|client| {
+        let block_hash = client.generate(1).wait().unwrap().remove(0);
+
+        client.get_block(&block_hash)
+    })
+}
+
+#[test]
+fn send_to_address() {
+    setup();
+
+    assert_successful_async_result(|client| {
+        client.generate(101).wait().unwrap();
+        let alice = client.get_new_address().wait().unwrap();
+
+        client.send_to_address(&alice, rpc::Amount::ONE_BTC)
+    })
+}
+
+#[test]
+fn call_unimplemented_rpc() {
+    setup();
+    assert_successful_async_result(|client| client.call::<u32>("getconnectioncount", &[]))
+}
@@ -1,13 +1,12 @@
-use bitcoin_rpc_client::BitcoinCoreClient;
-use jsonrpc_client::ClientError;
-use jsonrpc_client::RpcError;
+use bitcoin_rpc_client::{AsyncBitcoinCoreClient, BitcoinCoreClient, Error, RpcFuture};
 use std::fmt::Debug;
 use testcontainers::{clients::Cli, images::coblox_bitcoincore::BitcoinCore, Docker};
+use tokio::runtime::Runtime;
 
 pub fn assert_successful_result<R, I>(invocation: I)
 where
     R: Debug,
-    I: Fn(&BitcoinCoreClient) -> Result<Result<R, RpcError>, ClientError>,
+    I: Fn(&BitcoinCoreClient) -> Result<R, Error>,
 {
     let docker = Cli::default();
     let container = docker.run(BitcoinCore::default());
@@ -22,16 +21,38 @@ where
     };
 
     match invocation(&client) {
-        Ok(Ok(result)) => {
+        Ok(result) => {
             // Having a successful result means:
             // - No HTTP Error occured
             // - No deserialization error occured
+            // - No RPC error occured
             debug!("Returned result: {:?}", result)
         }
-        Ok(Err(rpc_error)) => panic!(
-            "Network call was successful but node returned rpc-error: {:?}",
-            rpc_error
-        ),
-        Err(http_error) => panic!("Failed to connect to node: {:?}", http_error),
+        Err(error) => panic!("Call failed: {:?}", error),
+    }
+}
+
+pub fn assert_successful_async_result<R, I>(invocation: I)
+where
+    R: Debug + Send + 'static,
+    I: Fn(&AsyncBitcoinCoreClient) -> RpcFuture<R>,
+{
+    let docker = Cli::default();
+    let container = docker.run(BitcoinCore::default());
+    let client = {
+        let host_port = container.get_host_port(18443).unwrap();
+
+        let url = format!("http://localhost:{}", host_port);
+
+        let auth = container.image().auth();
+
+        AsyncBitcoinCoreClient::new(url.as_str(), auth.username(), auth.password())
+    };
+
+    let mut runtime = Runtime::new().unwrap();
+
+    match runtime.block_on(invocation(&client)) {
+        Ok(result) => debug!("Returned result: {:?}", result),
+        Err(error) => panic!("Call failed: {:?}", error),
     }
 }
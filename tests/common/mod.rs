@@ -1,3 +1,2 @@
 pub mod assert;
-pub mod test_client;
 pub mod test_lifecycle;
@@ -1,5 +1,5 @@
 extern crate env_logger;
 
-pub fn setup() -> () {
+pub fn setup() {
     let _ = env_logger::try_init();
 }
@@ -0,0 +1,407 @@
+#![allow(deprecated)]
+extern crate bitcoin_rpc_client;
+extern crate reqwest;
+extern crate serde_json;
+extern crate tokio;
+
+use bitcoin_rpc_client::*;
+use reqwest::StatusCode;
+use serde_json::json;
+use std::{str::FromStr, time::Duration};
+use tokio::runtime::Runtime;
+
+fn a_server() -> MockServer {
+    MockServer::start("bitcoin", "54pLR_f7-G6is32LP-7nbhzZSbJs_2zSATtZV_r05yg=").unwrap()
+}
+
+fn a_client(server: &MockServer) -> BitcoinCoreClient {
+    BitcoinCoreClient::new(
+        &server.url(),
+        "bitcoin",
+        "54pLR_f7-G6is32LP-7nbhzZSbJs_2zSATtZV_r05yg=",
+    )
+    .with_retry_policy(RetryPolicy::none())
+}
+
+fn an_async_client(server: &MockServer) -> AsyncBitcoinCoreClient {
+    AsyncBitcoinCoreClient::new(
+        &server.url(),
+        "bitcoin",
+        "54pLR_f7-G6is32LP-7nbhzZSbJs_2zSATtZV_r05yg=",
+    )
+    .with_retry_policy(RetryPolicy::none())
+}
+
+#[test]
+fn sends_basic_auth_header() {
+    let server = a_server().with_response("getblockcount", MockResponse::result(42));
+
+    assert_eq!(
+        a_client(&server).get_block_count().unwrap(),
+        rpc::BlockHeight::new(42)
+    );
+    assert_eq!(
+        server.requests()[0].authorization,
+        Some(String::from(
+            "Basic Yml0Y29pbjo1NHBMUl9mNy1HNmlzMzJMUC03bmJoelpTYkpzXzJ6U0FUdFpWX3IwNXlnPQ=="
+        ))
+    );
+}
+
+#[test]
+fn fails_with_wrong_credentials() {
+    let server = a_server().with_response("getblockcount", MockResponse::result(42));
+    let client = BitcoinCoreClient::new(&server.url(), "bitcoin", "wrong")
+        .with_retry_policy(RetryPolicy::none());
+
+    match client.get_block_count() {
+        Err(Error::HttpStatus(StatusCode::UNAUTHORIZED)) => {}
+        outcome => panic!("expected 401 but got {:?}", outcome),
+    }
+    assert!(server.requests().is_empty());
+}
+
+#[test]
+fn retries_while_warming_up() {
+    let server = a_server()
+        .with_response(
+            "getblockcount",
+            MockResponse::error(-28, "Loading block index..."),
+        )
+        .with_response(
+            "getblockcount",
+            MockResponse::error(-28, "Verifying blocks..."),
+        )
+        .with_response("getblockcount", MockResponse::result(42));
+    let client = a_client(&server)
+        .with_retry_policy(RetryPolicy::new().with_initial_interval(Duration::from_millis(1)));
+
+    assert_eq!(client.get_block_count().unwrap(), rpc::BlockHeight::new(42));
+    assert_eq!(server.received_methods().len(), 3);
+}
+
+#[test]
+fn maps_rpc_errors() {
+    let server = a_server().with_response(
+        "sendrawtransaction",
+        MockResponse::error(-26, "66: min relay fee not met"),
+    );
+
+    let error = a_client(&server)
+        .send_raw_transaction(rpc::SerializedRawTransaction(String::from("0200000000")))
+        .unwrap_err();
+
+    assert_eq!(error.rpc_error_code(), Some(RpcErrorCode::VerifyRejected));
+    assert_eq!(error.reject_reason(), Some(RejectReason::InsufficientFee));
+}
+
+#[test]
+fn maps_unknown_methods_to_rpc_errors() {
+    let server = a_server();
+
+    let error = a_client(&server).get_best_block_hash().unwrap_err();
+
+    assert_eq!(error.rpc_error_code(), Some(RpcErrorCode::MethodNotFound));
+}
+
+#[test]
+fn maps_service_unavailable_to_http_status() {
+    let server = a_server().with_response("getblockcount", MockResponse::Status(503));
+
+    match a_client(&server).get_block_count() {
+        Err(Error::HttpStatus(StatusCode::SERVICE_UNAVAILABLE)) => {}
+        outcome => panic!("expected 503 but got {:?}", outcome),
+    }
+}
+
+#[test]
+fn fails_on_malformed_json() {
+    let server = a_server().with_response(
+        "getblockcount",
+        MockResponse::Body(String::from(r#"{"result":42,"#)),
+    );
+
+    match a_client(&server).get_block_count() {
+        Err(Error::Deserialization { ref method, .. }) if method == "getblockcount" => {}
+        outcome => panic!("expected a deserialization error but got {:?}", outcome),
+    }
+}
+
+#[test]
+fn sends_wallet_requests_to_wallet_endpoint() {
+    let server = a_server().with_response("getbalance", MockResponse::result(1.5));
+
+    a_client(&server).wallet("alice").get_balance().unwrap();
+
+    let request = &server.requests()[0];
+    assert_eq!(request.path, "/wallet/alice");
+    assert_eq!(request.params, json!(null));
+}
+
+#[test]
+fn batches_rpcs_with_typed_results() {
+    let hash = "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206";
+    let server = a_server()
+        .with_response("getblockhash", MockResponse::result(hash))
+        .with_response("getblockhash", MockResponse::result(42))
+        .with_response(
+            "getblockhash",
+            MockResponse::error(-8, "Block height out of range"),
+        );
+    let client = a_client(&server);
+    let requests = (0..3)
+        .map(|height| {
+            RpcRequest::new1(
+                JsonRpcVersion::V1,
+                &client.next_id(),
+                "getblockhash",
+                height,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let results = client.send_batch::<BlockHash, _>(&requests).unwrap();
+
+    assert_eq!(results[0].as_ref().unwrap().to_string(), hash);
+    match results[1] {
+        Err(Error::Deserialization { ref method, .. }) if method == "getblockhash" => {}
+        ref outcome => panic!("expected deserialization error but got {:?}", outcome),
+    }
+    assert_eq!(
+        results[2].as_ref().unwrap_err().rpc_error_code(),
+        Some(RpcErrorCode::InvalidParameter)
+    );
+    assert_eq!(
+        server.requests()[2].params,
+        json!([2]),
+        "params of each request are kept"
+    );
+}
+
+#[test]
+fn sends_newer_wallet_rpcs_to_wallet_endpoint() {
+    let server = a_server().with_response(
+        "getbalances",
+        MockResponse::result(json!({"mine": {"trusted": 1.5}})),
+    );
+
+    a_client(&server)
+        .wallet("alice")
+        .call::<serde_json::Value>("getbalances", &[])
+        .unwrap();
+
+    assert_eq!(server.requests()[0].path, "/wallet/alice");
+}
+
+#[test]
+fn retries_spending_rpcs_the_node_did_not_process() {
+    let txid = "52309405287e737cf412fc42883d65a392ab950869fae80b2a5f1e33326aca46";
+    let server = a_server()
+        .with_response(
+            "sendrawtransaction",
+            MockResponse::error(-28, "Loading block index..."),
+        )
+        .with_response("sendrawtransaction", MockResponse::Status(503))
+        .with_response("sendrawtransaction", MockResponse::result(txid));
+    let client = a_client(&server)
+        .with_retry_policy(RetryPolicy::new().with_initial_interval(Duration::from_millis(1)));
+
+    let sent = client
+        .send_raw_transaction(rpc::SerializedRawTransaction(String::from("0200000000")))
+        .unwrap();
+
+    assert_eq!(sent.to_string(), txid);
+    assert_eq!(server.received_methods().len(), 3);
+}
+
+fn a_node_of_version(version: u32) -> MockServer {
+    let block_hash = "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206";
+
+    a_server()
+        .with_response(
+            "getnetworkinfo",
+            MockResponse::result(json!({ "version": version })),
+        )
+        .with_response(
+            "getnewaddress",
+            MockResponse::result("mt17cV37fBqZsnMmrHnGCm9pM28R1kQdMG"),
+        )
+        .with_response("generate", MockResponse::result(json!([block_hash])))
+        .with_response(
+            "generatetoaddress",
+            MockResponse::result(json!([block_hash])),
+        )
+        .with_response(
+            "signrawtransaction",
+            MockResponse::result(json!({ "hex": "0200000000", "complete": true })),
+        )
+        .with_response(
+            "signrawtransactionwithkey",
+            MockResponse::result(json!({ "hex": "0200000000", "complete": true })),
+        )
+}
+
+fn a_private_key() -> rpc::PrivateKey {
+    rpc::PrivateKey::from(
+        PrivateKey::from_str("cQ1DDxScq1rsYDdCUBywawwNVWTMwnLzCKCwGndC6MgdNtKPQ5Hz").unwrap(),
+    )
+}
+
+#[test]
+fn generates_to_address_since_0_19() {
+    let server = a_node_of_version(190_000);
+
+    a_client(&server).generate(1).unwrap();
+
+    assert_eq!(
+        server.received_methods(),
+        vec!["getnetworkinfo", "getnewaddress", "generatetoaddress"]
+    );
+    assert_eq!(
+        server.requests()[2].params,
+        json!([1, "mt17cV37fBqZsnMmrHnGCm9pM28R1kQdMG"])
+    );
+}
+
+#[test]
+fn generates_with_wallet_before_0_19() {
+    let server = a_node_of_version(180_100);
+
+    a_client(&server).generate(1).unwrap();
+
+    assert_eq!(
+        server.received_methods(),
+        vec!["getnetworkinfo", "generate"]
+    );
+}
+
+#[test]
+fn generates_to_address_since_0_19_async() {
+    let server = a_node_of_version(190_000);
+
+    Runtime::new()
+        .unwrap()
+        .block_on(an_async_client(&server).generate(1))
+        .unwrap();
+
+    assert_eq!(
+        server.received_methods(),
+        vec!["getnetworkinfo", "getnewaddress", "generatetoaddress"]
+    );
+}
+
+#[test]
+fn rejects_address_types_before_0_16() {
+    let server = a_node_of_version(150_000);
+    let address = Address::from_str("mt17cV37fBqZsnMmrHnGCm9pM28R1kQdMG").unwrap();
+
+    match a_client(&server).get_address_info(&address) {
+        Err(Error::UnsupportedByNodeVersion {
+            ref method,
+            version,
+        }) if method == "getaddressinfo" && version == NodeVersion(150_000) => {}
+        outcome => panic!("expected unsupported version but got {:?}", outcome),
+    }
+    assert_eq!(server.received_methods(), vec!["getnetworkinfo"]);
+}
+
+#[test]
+fn rejects_address_types_before_0_16_async() {
+    let server = a_node_of_version(150_000);
+    let client = an_async_client(&server);
+    let address = Address::from_str("mt17cV37fBqZsnMmrHnGCm9pM28R1kQdMG").unwrap();
+    let mut runtime = Runtime::new().unwrap();
+
+    match runtime.block_on(client.get_address_info(&address)) {
+        Err(Error::UnsupportedByNodeVersion {
+            ref method,
+            version,
+        }) if method == "getaddressinfo" && version == NodeVersion(150_000) => {}
+        outcome => panic!("expected unsupported version but got {:?}", outcome),
+    }
+    match runtime.block_on(client.get_new_address()) {
+        Err(Error::UnsupportedByNodeVersion { ref method, .. }) if method == "getnewaddress" => {}
+        outcome => panic!("expected unsupported version but got {:?}", outcome),
+    }
+    assert_eq!(server.received_methods(), vec!["getnetworkinfo"]);
+}
+
+#[test]
+fn signs_with_signrawtransaction_before_0_17() {
+    let server = a_node_of_version(160_300);
+    let key = a_private_key();
+
+    a_client(&server)
+        .sign_raw_transaction_with_key(
+            &rpc::SerializedRawTransaction(String::from("0200000000")),
+            Some(vec![&key]),
+            None,
+            None,
+        )
+        .unwrap();
+
+    assert_eq!(
+        server.received_methods(),
+        vec!["getnetworkinfo", "signrawtransaction"]
+    );
+    assert_eq!(
+        server.requests()[1].params,
+        json!([
+            "0200000000",
+            null,
+            ["cQ1DDxScq1rsYDdCUBywawwNVWTMwnLzCKCwGndC6MgdNtKPQ5Hz"],
+            null
+        ])
+    );
+}
+
+#[test]
+fn signs_with_signrawtransaction_before_0_17_async() {
+    let server = a_node_of_version(160_300);
+    let key = a_private_key();
+
+    Runtime::new()
+        .unwrap()
+        .block_on(an_async_client(&server).sign_raw_transaction_with_key(
+            &rpc::SerializedRawTransaction(String::from("0200000000")),
+            Some(vec![&key]),
+            None,
+            None,
+        ))
+        .unwrap();
+
+    assert_eq!(
+        server.received_methods(),
+        vec!["getnetworkinfo", "signrawtransaction"]
+    );
+    assert_eq!(
+        server.requests()[1].params,
+        json!([
+            "0200000000",
+            null,
+            ["cQ1DDxScq1rsYDdCUBywawwNVWTMwnLzCKCwGndC6MgdNtKPQ5Hz"],
+            null
+        ])
+    );
+}
+
+#[test]
+fn signs_with_key_since_0_17_async() {
+    let server = a_node_of_version(170_000);
+    let key = a_private_key();
+
+    Runtime::new()
+        .unwrap()
+        .block_on(an_async_client(&server).sign_raw_transaction_with_key(
+            &rpc::SerializedRawTransaction(String::from("0200000000")),
+            Some(vec![&key]),
+            None,
+            None,
+        ))
+        .unwrap();
+
+    assert_eq!(
+        server.received_methods(),
+        vec!["getnetworkinfo", "signrawtransactionwithkey"]
+    );
+}
@@ -0,0 +1,39 @@
+#![cfg(feature = "regtest")]
+#![allow(deprecated)]
+
+extern crate bitcoin_rpc_client;
+
+use bitcoin_rpc_client::*;
+
+#[test]
+fn provides_funded_wallet_and_cleans_up() {
+    let node = RegtestNode::start().expect("bitcoind must be installed to run this test");
+
+    let utxo = node.a_utxo();
+    assert!(utxo.amount > rpc::Amount::ZERO);
+
+    let txid = node
+        .client()
+        .send_to_address(&node.an_address(), rpc::Amount::ONE_BTC)
+        .unwrap();
+    let transaction = node.client().get_raw_transaction_verbose(&txid).unwrap();
+    assert_eq!(transaction.txid, txid);
+
+    let datadir = node.datadir().to_path_buf();
+    drop(node);
+    assert!(!datadir.exists());
+}
+
+#[test]
+fn runs_nodes_side_by_side() {
+    let first = RegtestNode::start().unwrap();
+    let second = RegtestNode::start().unwrap();
+
+    assert_ne!(first.url(), second.url());
+
+    first.a_block();
+    assert_eq!(
+        second.client().get_block_count().unwrap(),
+        rpc::BlockHeight::new(0)
+    );
+}
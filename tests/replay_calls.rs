@@ -0,0 +1,113 @@
+#![allow(deprecated)]
+extern crate bitcoin_rpc_client;
+
+use bitcoin_rpc_client::*;
+use std::{env, fs, process, time::Duration};
+
+const FIXTURE: &str = "tests/fixtures/replay_calls.json";
+
+fn replaying_client() -> BitcoinCoreClient {
+    BitcoinCoreClient::from_fixture(FIXTURE).unwrap()
+}
+
+fn a_malformed_transaction() -> rpc::SerializedRawTransaction {
+    rpc::SerializedRawTransaction(String::from("0200000000"))
+}
+
+/// The release the fixture is recorded against, the oldest one the client supports.
+const FIXTURE_VERSION: NodeVersion = NodeVersion(170_100);
+
+/// Records the fixture against the bitcoind 0.17.1 in `BITCOIND_EXE`:
+/// `cargo test --features regtest --test replay_calls -- --ignored record_fixture`
+#[cfg(feature = "regtest")]
+#[test]
+#[ignore]
+fn record_fixture() {
+    let node = RegtestNode::start().unwrap();
+    assert_eq!(
+        node.client().node_version().unwrap(),
+        FIXTURE_VERSION,
+        "BITCOIND_EXE is not bitcoind 0.17.1"
+    );
+    let client = node.new_client().with_recording(FIXTURE);
+
+    client.generate(1).unwrap();
+    client.get_block_count().unwrap();
+    client.get_new_address().unwrap();
+    client
+        .send_raw_transaction(a_malformed_transaction())
+        .unwrap_err();
+}
+
+#[test]
+fn replays_recorded_calls() {
+    let client = replaying_client();
+
+    assert_eq!(client.generate(1).unwrap().len(), 1);
+    assert_eq!(client.get_block_count().unwrap(), rpc::BlockHeight::new(1));
+    assert_eq!(client.get_new_address().unwrap().network, Network::Regtest);
+    assert_eq!(client.node_version().unwrap(), FIXTURE_VERSION);
+}
+
+#[test]
+fn replays_recorded_rpc_errors() {
+    let client = replaying_client();
+
+    let error = client
+        .send_raw_transaction(a_malformed_transaction())
+        .unwrap_err();
+
+    assert_eq!(
+        error.rpc_error_code(),
+        Some(RpcErrorCode::DeserializationError)
+    );
+}
+
+#[test]
+fn replays_recorded_http_errors() {
+    let server = MockServer::start("bitcoin", "secret")
+        .unwrap()
+        .with_response("getblockcount", MockResponse::Status(503));
+    let path = env::temp_dir().join(format!(
+        "replays_recorded_http_errors-{}.json",
+        process::id()
+    ));
+    let recording = BitcoinCoreClient::new(&server.url(), "bitcoin", "secret")
+        .with_retry_policy(RetryPolicy::none())
+        .with_recording(&path);
+
+    assert!(recording.get_block_count().is_err());
+
+    let replaying = BitcoinCoreClient::from_fixture(&path)
+        .unwrap()
+        .with_retry_policy(RetryPolicy::none());
+    fs::remove_file(&path).unwrap();
+
+    match replaying.get_block_count() {
+        Err(Error::HttpStatus(status)) => assert_eq!(status.as_u16(), 503),
+        outcome => panic!("expected 503 but got {:?}", outcome),
+    }
+}
+
+#[test]
+fn fails_on_requests_that_were_not_recorded() {
+    let client = replaying_client();
+
+    match client.get_best_block_hash() {
+        Err(Error::UnexpectedRequest { .. }) => {}
+        outcome => panic!("expected an unexpected request but got {:?}", outcome),
+    }
+}
+
+#[test]
+fn retries_injected_warm_up_errors() {
+    let client = replaying_client()
+        .with_retry_policy(
+            RetryPolicy::new()
+                .with_initial_interval(Duration::from_millis(1))
+                .with_max_retries(20),
+        )
+        .with_faults(Faults::new(42).with_fault(Fault::WarmingUp, 0.5));
+
+    assert_eq!(client.get_block_count().unwrap(), rpc::BlockHeight::new(1));
+}
@@ -0,0 +1,508 @@
+extern crate bitcoin_rpc_client;
+extern crate serde;
+extern crate serde_json;
+extern crate serde_path_to_error;
+
+use bitcoin_rpc_client::*;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Map, Value};
+use std::{collections::BTreeSet, fs, path::Path};
+
+/// Responses of every implemented RPC as returned by one Bitcoin Core release, keyed by RPC.
+///
+/// Verbose variants of an RPC are stored under `<rpc>_verbose`.
+struct Corpus {
+    version: String,
+    responses: Map<String, Value>,
+    checked: BTreeSet<String>,
+    failures: Vec<String>,
+}
+
+impl Corpus {
+    fn load(path: &Path) -> Self {
+        let version = path.file_stem().unwrap().to_string_lossy().into_owned();
+        let responses = serde_json::from_str(&fs::read_to_string(path).unwrap())
+            .unwrap_or_else(|e| panic!("{} is not a JSON object: {}", path.display(), e));
+
+        Corpus {
+            version,
+            responses,
+            checked: BTreeSet::new(),
+            failures: Vec::new(),
+        }
+    }
+
+    fn all() -> Vec<Corpus> {
+        let mut paths = fs::read_dir("tests/fixtures/responses")
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        paths.iter().map(|path| Corpus::load(path)).collect()
+    }
+
+    /// Major version of the release, e.g. 17 for 0.17.1 and 28 for 28.1.
+    fn major_version(&self) -> u32 {
+        let mut parts = self.version.split('.').map(|part| part.parse().unwrap());
+
+        match parts.next() {
+            Some(0) => parts.next().unwrap(),
+            Some(major) => major,
+            None => unreachable!(),
+        }
+    }
+
+    /// Deserializes the response to `rpc` and checks that it serializes back to what bitcoind sent,
+    /// except for the `IGNORED_FIELDS` of `rpc`.
+    fn deserialize<T: DeserializeOwned + Serialize>(&mut self, rpc: &str) -> Option<T> {
+        self.checked.insert(rpc.to_string());
+
+        let response = match self.responses.get(rpc) {
+            Some(response) => response.to_string(),
+            None => {
+                self.failures
+                    .push(format!("{}: no response to {}", self.version, rpc));
+                return None;
+            }
+        };
+        let mut deserializer = serde_json::Deserializer::from_str(&response);
+
+        match serde_path_to_error::deserialize(&mut deserializer) {
+            Ok(deserialized) => {
+                let serialized = serde_json::to_value(&deserialized).unwrap();
+
+                let expected = expected(rpc, &self.responses[rpc]);
+
+                if let Some(path) = difference(&serialized, &expected, rpc) {
+                    self.failures.push(format!(
+                        "{}: serialized {} differs from bitcoind at {}",
+                        self.version, rpc, path
+                    ));
+                }
+
+                Some(deserialized)
+            }
+            Err(e) => {
+                self.failures.push(format!(
+                    "{}: failed to deserialize {} at {}: {}",
+                    self.version,
+                    rpc,
+                    e.path(),
+                    e.inner()
+                ));
+                None
+            }
+        }
+    }
+
+    fn deserialize_all(&mut self) {
+        self.deserialize::<rpc::MultiSigAddress>("addmultisigaddress");
+        self.deserialize::<rpc::SerializedRawTransaction>("createrawtransaction");
+        self.deserialize::<rpc::DecodedRawTransaction>("decoderawtransaction");
+        self.deserialize::<rpc::DecodedScript>("decodescript");
+        self.deserialize::<rpc::PrivateKey>("dumpprivkey");
+        self.deserialize::<rpc::FundingResult>("fundrawtransaction");
+        // Removed in 0.19
+        if self.major_version() < 19 {
+            self.deserialize::<Vec<BlockHash>>("generate");
+        }
+        self.deserialize::<Vec<BlockHash>>("generatetoaddress");
+        self.deserialize::<rpc::AddressInfoResult>("getaddressinfo");
+        self.deserialize::<rpc::Amount>("getbalance");
+        self.deserialize::<BlockHash>("getbestblockhash");
+        self.deserialize::<rpc::Block<TransactionId>>("getblock");
+        self.deserialize::<rpc::Block<rpc::DecodedRawTransaction>>("getblock_verbose");
+        self.deserialize::<rpc::BlockchainInfo>("getblockchaininfo");
+        self.deserialize::<rpc::BlockHeight>("getblockcount");
+        self.deserialize::<BlockHash>("getblockhash");
+        self.deserialize::<Address>("getnewaddress");
+        self.deserialize::<rpc::SerializedRawTransaction>("getrawtransaction");
+        self.deserialize::<rpc::VerboseRawTransaction>("getrawtransaction_verbose");
+        self.deserialize::<Vec<rpc::UnspentTransactionOutput>>("listunspent");
+        self.deserialize::<TransactionId>("sendrawtransaction");
+        self.deserialize::<TransactionId>("sendtoaddress");
+        self.deserialize::<rpc::SigningResult>("signrawtransactionwithkey");
+        self.deserialize::<rpc::AddressValidationResult>("validateaddress");
+    }
+
+    fn unchecked(&self) -> Vec<String> {
+        self.responses
+            .keys()
+            .filter(|rpc| !self.checked.contains(*rpc))
+            .map(|rpc| format!("{}: no test for {}", self.version, rpc))
+            .collect()
+    }
+}
+
+/// Fields of bitcoind's responses that a type deliberately doesn't model, by RPC.
+///
+/// Paths are relative to the result, `*` stands for every element of an array.
+const IGNORED_FIELDS: &[(&str, &[&str])] = &[
+    ("addmultisigaddress", &["descriptor"]),
+    (
+        "decoderawtransaction",
+        &["vout.*.scriptPubKey.desc", "weight"],
+    ),
+    ("decodescript", &["desc", "segwit"]),
+    (
+        "getaddressinfo",
+        &[
+            "desc",
+            "hdmasterfingerprint",
+            "ischange",
+            "parent_desc",
+            "solvable",
+        ],
+    ),
+    ("getblock", &["nTx"]),
+    (
+        "getblock_verbose",
+        &[
+            "nTx",
+            "tx.*.hex",
+            "tx.*.vout.*.scriptPubKey.desc",
+            "tx.*.weight",
+        ],
+    ),
+    (
+        "getblockchaininfo",
+        &[
+            "bip9_softforks.testdummy",
+            "softforks.taproot.bip9.min_activation_height",
+            "softforks.testdummy.bip9.min_activation_height",
+            "time",
+        ],
+    ),
+    (
+        "getrawtransaction_verbose",
+        &["in_active_chain", "vout.*.scriptPubKey.desc", "weight"],
+    ),
+    ("listunspent", &["*.desc", "*.label", "*.parent_descs"]),
+    (
+        "validateaddress",
+        &[
+            "hdseedid",
+            "iswitness",
+            "label",
+            "labels",
+            "timestamp",
+            "witness_program",
+            "witness_version",
+        ],
+    ),
+];
+
+/// What bitcoind sent without the ignored fields of `rpc`, and with warnings as a list like 28.0
+/// and later send them.
+fn expected(rpc: &str, sent: &Value) -> Value {
+    let mut expected = sent.clone();
+
+    for &(_, paths) in IGNORED_FIELDS
+        .iter()
+        .filter(|&&(ignored, _)| ignored == rpc)
+    {
+        for path in paths.iter() {
+            remove(&mut expected, &path.split('.').collect::<Vec<_>>());
+        }
+    }
+
+    if let Some(warnings) = expected.get_mut("warnings") {
+        if let Value::String(warning) = warnings.clone() {
+            *warnings = if warning.is_empty() {
+                json!([])
+            } else {
+                json!([warning])
+            };
+        }
+    }
+
+    expected
+}
+
+fn remove(value: &mut Value, path: &[&str]) {
+    match (value, path.split_first()) {
+        (&mut Value::Array(ref mut elements), Some((&"*", rest))) => {
+            for element in elements {
+                remove(element, rest);
+            }
+        }
+        (&mut Value::Object(ref mut fields), Some((field, []))) => {
+            fields.remove(*field);
+        }
+        (&mut Value::Object(ref mut fields), Some((field, rest))) => {
+            if let Some(value) = fields.get_mut(*field) {
+                remove(value, rest);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The path to the first field in which `serialized` differs from `expected`, if any.
+fn difference(serialized: &Value, expected: &Value, path: &str) -> Option<String> {
+    match (serialized, expected) {
+        (Value::Object(serialized), Value::Object(expected)) => {
+            let keys = serialized
+                .keys()
+                .chain(expected.keys())
+                .collect::<BTreeSet<_>>();
+
+            keys.into_iter().find_map(|key| {
+                let path = format!("{}.{}", path, key);
+
+                match (serialized.get(key), expected.get(key)) {
+                    (Some(serialized), Some(expected)) => difference(serialized, expected, &path),
+                    _ => Some(path),
+                }
+            })
+        }
+        (Value::Array(serialized), Value::Array(expected))
+            if serialized.len() == expected.len() =>
+        {
+            serialized.iter().zip(expected).enumerate().find_map(
+                |(index, (serialized, expected))| {
+                    difference(serialized, expected, &format!("{}[{}]", path, index))
+                },
+            )
+        }
+        // Amounts are serialized with as few decimals as needed
+        (Value::Number(serialized), Value::Number(expected))
+            if serialized.as_f64() == expected.as_f64() =>
+        {
+            None
+        }
+        (serialized, expected) if serialized == expected => None,
+        _ => Some(path.to_string()),
+    }
+}
+
+#[test]
+fn compares_all_fields_but_ignored_ones() {
+    let sent = json!({"hash": "00", "nTx": 1, "size": 2, "warnings": "a warning"});
+    let expected = expected("getblock", &sent);
+
+    assert_eq!(
+        expected,
+        json!({"hash": "00", "size": 2, "warnings": ["a warning"]})
+    );
+    assert_eq!(
+        difference(
+            &json!({"hash": "00", "warnings": ["a warning"]}),
+            &expected,
+            "getblock"
+        ),
+        Some(String::from("getblock.size"))
+    );
+    assert_eq!(
+        difference(
+            &json!({"hash": "00", "size": 2.0, "warnings": ["a warning"]}),
+            &expected,
+            "getblock"
+        ),
+        None
+    );
+}
+
+#[test]
+fn deserializes_responses_of_all_versions() {
+    let mut failures = Vec::new();
+
+    for mut corpus in Corpus::all() {
+        corpus.deserialize_all();
+
+        failures.extend(corpus.unchecked());
+        failures.extend(corpus.failures);
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn covers_releases_since_0_17() {
+    let majors = Corpus::all()
+        .iter()
+        .map(Corpus::major_version)
+        .collect::<Vec<_>>();
+
+    assert_eq!(majors.first(), Some(&17));
+    assert!(majors.len() >= 10);
+}
+
+#[test]
+fn follows_softfork_layout_of_each_version() {
+    for mut corpus in Corpus::all() {
+        let info = corpus
+            .deserialize::<rpc::BlockchainInfo>("getblockchaininfo")
+            .unwrap();
+
+        match (corpus.major_version(), info.softforks) {
+            (0..=18, Some(rpc::SoftForks::Legacy(_))) => assert!(info.bip9_softforks.is_some()),
+            (19..=22, Some(rpc::SoftForks::Deployments(deployments))) => {
+                assert!(deployments.contains_key("segwit"));
+                assert!(info.bip9_softforks.is_none());
+            }
+            (23..=99, None) => assert!(info.bip9_softforks.is_none()),
+            (_, softforks) => panic!("{}: unexpected softforks {:?}", corpus.version, softforks),
+        }
+    }
+}
+
+#[test]
+fn reads_addresses_of_outputs_of_all_versions() {
+    for mut corpus in Corpus::all() {
+        let transaction = corpus
+            .deserialize::<rpc::DecodedRawTransaction>("decoderawtransaction")
+            .unwrap();
+        let script_pub_key = &transaction.vout[0].script_pub_key;
+
+        let address = if corpus.major_version() < 22 {
+            script_pub_key
+                .addresses
+                .as_ref()
+                .map(|addresses| &addresses[0])
+        } else {
+            script_pub_key.address.as_ref()
+        };
+
+        assert_eq!(
+            address.map(ToString::to_string),
+            Some(String::from("bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080")),
+            "{}",
+            corpus.version
+        );
+    }
+}
+
+/// Records the corpus of the bitcoind in `BITCOIND_EXE` to `tests/fixtures/responses`:
+/// `cargo test --features regtest --test response_corpus -- --ignored record_corpus`
+///
+/// The wallet RPCs use a legacy wallet since descriptor wallets, the default since 23.0, don't
+/// provide `dumpprivkey` and `addmultisigaddress`.
+#[cfg(feature = "regtest")]
+#[test]
+#[ignore]
+fn record_corpus() {
+    let node = RegtestNode::start_with_args(&["-deprecatedrpc=create_bdb"]).unwrap();
+    let version = node.client().node_version().unwrap();
+    let (major, minor) = (version.0 / 10_000, version.0 / 100 % 100);
+    let client = if version >= NodeVersion(210_000) {
+        let legacy = [
+            json!("legacy"),
+            json!(false),
+            json!(false),
+            json!(""),
+            json!(false),
+            json!(false),
+        ];
+        node.client()
+            .call::<Value>("createwallet", &legacy)
+            .unwrap();
+
+        node.new_client().wallet("legacy")
+    } else {
+        node.new_client()
+    };
+    let call = |method: &str, params: Vec<Value>| {
+        client
+            .call::<Value>(method, &params)
+            .unwrap_or_else(|e| panic!("{} failed: {}", method, e))
+    };
+
+    let mut responses = Map::new();
+    let mut record = |rpc: &str, method: &str, params: Vec<Value>| {
+        let response = call(method, params);
+        responses.insert(rpc.to_string(), response.clone());
+
+        response
+    };
+
+    let address = record("getnewaddress", "getnewaddress", vec![]);
+    call("generatetoaddress", vec![json!(101), address.clone()]);
+    if major < 19 {
+        record("generate", "generate", vec![json!(1)]);
+    }
+    record(
+        "generatetoaddress",
+        "generatetoaddress",
+        vec![json!(1), address.clone()],
+    );
+    record("getbestblockhash", "getbestblockhash", vec![]);
+    record("getblockcount", "getblockcount", vec![]);
+    let block_hash = record("getblockhash", "getblockhash", vec![json!(1)]);
+    record("getblock", "getblock", vec![block_hash.clone()]);
+    record("getblock_verbose", "getblock", vec![block_hash, json!(2)]);
+    record("getblockchaininfo", "getblockchaininfo", vec![]);
+    record("getbalance", "getbalance", vec![]);
+    let utxo = record("listunspent", "listunspent", vec![])[0].clone();
+    record("getaddressinfo", "getaddressinfo", vec![address.clone()]);
+    record("validateaddress", "validateaddress", vec![address.clone()]);
+    let private_key = record("dumpprivkey", "dumpprivkey", vec![address.clone()]);
+
+    let other_address = call("getnewaddress", vec![]);
+    let multisig = record(
+        "addmultisigaddress",
+        "addmultisigaddress",
+        vec![json!(1), json!([address, other_address])],
+    );
+    record(
+        "decodescript",
+        "decodescript",
+        vec![multisig["redeemScript"].clone()],
+    );
+
+    // `reads_addresses_of_outputs_of_all_versions` expects this address
+    let mut outputs = Map::new();
+    let amount = utxo["amount"].as_f64().unwrap() - 0.001;
+    outputs.insert(
+        String::from("bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080"),
+        json!((amount * 1e8).round() / 1e8),
+    );
+    let inputs = json!([{"txid": utxo["txid"], "vout": utxo["vout"]}]);
+    let transaction = record(
+        "createrawtransaction",
+        "createrawtransaction",
+        vec![inputs, Value::Object(outputs)],
+    );
+    record(
+        "decoderawtransaction",
+        "decoderawtransaction",
+        vec![transaction.clone()],
+    );
+    let signed = record(
+        "signrawtransactionwithkey",
+        "signrawtransactionwithkey",
+        vec![transaction, json!([private_key])],
+    );
+    let txid = record(
+        "sendrawtransaction",
+        "sendrawtransaction",
+        vec![signed["hex"].clone()],
+    );
+    call("generatetoaddress", vec![json!(1), other_address.clone()]);
+    record("getrawtransaction", "getrawtransaction", vec![txid.clone()]);
+    record(
+        "getrawtransaction_verbose",
+        "getrawtransaction",
+        vec![txid, json!(true)],
+    );
+
+    let unfunded = call(
+        "createrawtransaction",
+        vec![json!([]), json!({ other_address.as_str().unwrap(): 1 })],
+    );
+    record("fundrawtransaction", "fundrawtransaction", vec![unfunded]);
+    record(
+        "sendtoaddress",
+        "sendtoaddress",
+        vec![other_address, json!(1)],
+    );
+
+    let name = if major < 22 {
+        format!("0.{}.{}", major, minor)
+    } else {
+        format!("{}.{}", major, minor)
+    };
+    let path = Path::new("tests/fixtures/responses").join(format!("{}.json", name));
+    fs::write(&path, serde_json::to_string_pretty(&responses).unwrap()).unwrap();
+}
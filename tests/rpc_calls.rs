@@ -5,14 +5,16 @@ extern crate jsonrpc_client;
 extern crate log;
 extern crate bitcoin;
 extern crate hex;
+#[macro_use]
+extern crate serde_json;
 extern crate testcontainers;
+extern crate tokio;
 
 use bitcoin_rpc_client::*;
-use common::{
-    assert::assert_successful_result, test_client::BitcoinCoreTestClient, test_lifecycle::setup,
-};
+use common::{assert::assert_successful_result, test_lifecycle::setup};
 use std::collections::HashMap;
 
+#[allow(dead_code)]
 mod common;
 
 #[test]
@@ -70,7 +72,7 @@ fn generate() {
 fn get_balance() {
     setup();
     assert_successful_result(|client| {
-        || client.generate(101).unwrap().unwrap();
+        client.generate(101).unwrap();
         client.get_balance()
     })
 }
@@ -151,7 +153,6 @@ fn validate_multisig_address() {
         let multi_sig = client
             .add_multisig_address(1, vec![&alice, &bob])
             .unwrap()
-            .unwrap()
             .address;
 
         client.validate_address(&multi_sig)
@@ -229,7 +230,7 @@ fn sign_raw_transaction() {
         let test_client = BitcoinCoreTestClient::new(client);
 
         let alice = test_client.an_address();
-        let alice_private_key = test_client.client.dump_privkey(&alice).unwrap().unwrap();
+        let alice_private_key = test_client.client.dump_privkey(&alice).unwrap();
 
         let utxo = test_client.a_utxo();
 
@@ -240,7 +241,6 @@ fn sign_raw_transaction() {
         let tx = test_client
             .client
             .create_raw_transaction(vec![&input], &map)
-            .unwrap()
             .unwrap();
 
         client.sign_raw_transaction_with_key(
@@ -261,7 +261,7 @@ fn send_to_address() {
         test_client.a_block();
         let alice = test_client.an_address();
 
-        client.send_to_address(&alice, 1.0)
+        client.send_to_address(&alice, rpc::Amount::ONE_BTC)
     })
 }
 
@@ -277,15 +277,62 @@ fn fund_raw_transaction() {
         let alice = test_client.an_address();
 
         let mut outputs = HashMap::new();
-        outputs.insert(alice, 10f64);
+        outputs.insert(alice, rpc::Amount::from_sat(1_000_000_000));
 
         let raw_tx = test_client
             .client
             .create_raw_transaction(Vec::new(), &outputs)
-            .unwrap()
             .unwrap();
         let options = rpc::FundingOptions::new();
 
         client.fund_raw_transaction(&raw_tx, &options)
     })
 }
+
+#[test]
+fn send_batch() {
+    setup();
+
+    assert_successful_result(|client| {
+        BitcoinCoreTestClient::new(client).a_block();
+
+        let requests = (0..10)
+            .map(|height| {
+                RpcRequest::new1(
+                    JsonRpcVersion::V1,
+                    &client.next_id(),
+                    "getblockhash",
+                    height,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        client
+            .send_batch::<BlockHash, _>(&requests)
+            .and_then(|results| results.into_iter().collect::<Result<Vec<_>, _>>())
+    })
+}
+
+#[test]
+fn get_balance_of_default_wallet() {
+    setup();
+
+    assert_successful_result(|client| {
+        let wallet = client.wallet("");
+
+        BitcoinCoreTestClient::new(&wallet).a_block();
+
+        wallet.get_balance()
+    })
+}
+
+#[test]
+fn call_unimplemented_rpc() {
+    setup();
+
+    assert_successful_result(|client| {
+        let block_hash = BitcoinCoreTestClient::new(client).a_block_hash();
+
+        client.call::<serde_json::Value>("getblockheader", &[json!(block_hash)])
+    })
+}